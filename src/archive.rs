@@ -0,0 +1,53 @@
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::Path;
+
+// The compressed tarball formats that a release can be published in, named by
+// their compression, and written as in the {{archive-format}} of a download url template
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "tar.zst")]
+    Zst,
+    #[serde(rename = "tar.xz")]
+    Xz,
+    #[serde(rename = "tar.gz")]
+    Gz,
+}
+
+impl ArchiveFormat {
+    // Smallest, and so fastest to download, first
+    pub const PREFERRED: [ArchiveFormat; 3] = [Self::Zst, Self::Xz, Self::Gz];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Zst => "tar.zst",
+            Self::Xz => "tar.xz",
+            Self::Gz => "tar.gz",
+        }
+    }
+
+    // The format of an archive, from the extension of its filename
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let filename = path.file_name()?.to_str()?;
+        Self::PREFERRED
+            .into_iter()
+            .find(|format| filename.ends_with(&format!(".{}", format.extension())))
+    }
+
+    // Decompresses an archive in this format into a tarball
+    pub fn decoder<'a>(self, reader: impl Read + 'a) -> std::io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::Zst => Box::new(zstd::Decoder::new(reader)?),
+            Self::Xz => Box::new(liblzma::read::XzDecoder::new(reader)),
+            Self::Gz => Box::new(GzDecoder::new(reader)),
+        })
+    }
+}
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.extension())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::dfx::SHIM_PREFIX;
 use crate::error::cli::DetermineModeError::{NoExeName, UnrecognizedExeName};
 use crate::error::cli::{DetermineModeError, DispatchError};
 use crate::locations::Locations;
@@ -20,7 +21,7 @@ pub async fn dispatch(args: &[OsString]) -> Result<ExitCode, DispatchError> {
     let exit_code = match determine_mode(args)? {
         Init => dfxvm_init::main(args, &locations).await?,
         Manage => dfxvm::main(args, &locations).await?,
-        Proxy => dfx::main(args, &locations)?,
+        Proxy => dfx::main(args, &locations).await?,
     };
     Ok(exit_code)
 }
@@ -36,6 +37,7 @@ fn determine_mode(args: &[OsString]) -> Result<Mode, DetermineModeError> {
 
     match process_name.as_deref() {
         Some("dfx") => Ok(Proxy),
+        Some(n) if n.starts_with(SHIM_PREFIX) => Ok(Proxy),
         Some("dfxvm") => Ok(Manage),
         Some(n) if n.starts_with("dfxvm-init") => {
             // NB: The above check is only for the prefix of the file
@@ -1,25 +1,36 @@
-use crate::dfxvm::cleanup_self_updater;
+mod version;
+
+use crate::dfxvm::{
+    cleanup_self_updater, install, installed_target, recover_interrupted_operations,
+};
 use crate::env::prepend_to_path;
 use crate::error::dfx;
 use crate::error::dfx::Error::Exec;
 use crate::error::dfx::{
-    DetermineDfxVersionError, FindDfxJsonError, GetVersionFromCommandLineError,
-    GetVersionFromDfxJsonError, GetVersionFromEnvironmentError,
+    AutoInstallError, DetermineDfxVersionError, GetAutoInstallModeError,
+    GetVersionFromCommandLineError,
 };
-use crate::error::json::LoadJsonFileError;
-use crate::json::load_json_file;
+use crate::error::dfxvm_init::InteractError;
 use crate::locations::Locations;
-use crate::settings::Settings;
+use crate::settings::{AutoInstall, Settings};
 use crate::style::style_command;
+use crate::target::Target;
+use dialoguer::Confirm;
 use semver::Version;
-use serde::Deserialize;
 use std::ffi::OsString;
+use std::io::IsTerminal;
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::process::CommandExt;
-use std::path::PathBuf;
-use std::process::ExitCode;
+use std::process::{Command, ExitCode};
+
+pub use version::{
+    determine_dfx_version, find_dfx_json, get_version_from_dfx_json, get_version_from_environment,
+    get_version_from_override, get_version_from_program_name, get_version_from_settings,
+    get_version_from_toolchain_file, is_toolchain_name, DfxVersionSpec, SourcedVersion, Toolchain,
+    VersionOrTag, SHIM_PREFIX,
+};
 
-pub fn main(args: &[OsString], locations: &Locations) -> Result<ExitCode, dfx::Error> {
+pub async fn main(args: &[OsString], locations: &Locations) -> Result<ExitCode, dfx::Error> {
     if trying_to_call_dfx_upgrade(args) {
         err!("The command `dfx upgrade` doesn't work with dfxvm.");
         err!("To upgrade dfx, run:");
@@ -27,23 +38,49 @@ pub fn main(args: &[OsString], locations: &Locations) -> Result<ExitCode, dfx::E
         return Ok(ExitCode::FAILURE);
     }
     cleanup_self_updater(locations)?;
-    let Some((version, args)) = get_dfx_version_and_command_args(args, locations)? else {
+    recover_interrupted_operations(locations);
+    let Some((toolchain, args)) = get_dfx_version_and_command_args(args, locations).await? else {
         err!("Unable to determine which dfx version to call. To set a default version, run:");
         err!("    {}", style_command("dfxvm default <version>"));
         return Ok(ExitCode::FAILURE);
     };
 
-    let bin_path = locations.dfx_bin_path(&version);
+    auto_install(&toolchain, locations).await?;
+    let bin_path = locations.dfx_bin_path(&toolchain);
     if !bin_path.exists() {
-        err!("dfx {version} is not installed.  To install it, run:");
-        err!("    {}", style_command(&format!("dfxvm install {version}")));
+        if let Toolchain::Custom(name) = &toolchain {
+            err!("dfx {name} is not linked to an existing dfx binary.  To link it, run:");
+            err!(
+                "    {}",
+                style_command(&format!("dfxvm link {name} <path>"))
+            );
+        } else {
+            err!("dfx {toolchain} is not installed.  To install it, run:");
+            err!(
+                "    {}",
+                style_command(&format!("dfxvm install {toolchain}"))
+            );
+        }
         return Ok(ExitCode::FAILURE);
     }
+    if let Toolchain::Release(version) = &toolchain {
+        if let Some(target) = installed_target(&toolchain, locations).filter(|t| !t.runs_here()) {
+            let host = Target::host_for_dfx(version);
+            err!("dfx {version} is installed for {target}, so it can't run on this machine, which is {host}.");
+            err!("To install it for this machine, run:");
+            err!(
+                "    {}",
+                style_command(&format!(
+                    "dfxvm uninstall {version} && dfxvm install {version} --target {host}"
+                ))
+            );
+            return Ok(ExitCode::FAILURE);
+        }
+    }
 
-    let mut command = std::process::Command::new(bin_path);
+    let mut command = Command::new(bin_path);
     command.args(args);
-    command.env("DFX_VERSION", version.to_string());
-    command.env("PATH", prepend_to_path(&locations.version_dir(&version)));
+    set_dfx_env(&mut command, &toolchain, locations);
     let err = command.exec();
     Err(Exec {
         command: Box::new(command),
@@ -51,6 +88,59 @@ pub fn main(args: &[OsString], locations: &Locations) -> Result<ExitCode, dfx::E
     })
 }
 
+// Sets DFX_VERSION, and puts the dfx version's directory first on PATH,
+// for a command that runs dfx directly or through child processes.
+pub fn set_dfx_env(command: &mut Command, toolchain: &Toolchain, locations: &Locations) {
+    command.env("DFX_VERSION", toolchain.to_string());
+    command.env("PATH", prepend_to_path(&locations.toolchain_dir(toolchain)));
+}
+
+// Installs a dfx version that is not installed, if auto-install is enabled.
+// Linked toolchains are never installed.  The version is about to run, so it
+// is installed for this machine, whatever target the settings name.
+pub async fn auto_install(
+    toolchain: &Toolchain,
+    locations: &Locations,
+) -> Result<(), AutoInstallError> {
+    let Toolchain::Release(version) = toolchain else {
+        return Ok(());
+    };
+    if !locations.dfx_bin_path(toolchain).exists() && should_auto_install(version, locations)? {
+        let host = Target::host_for_dfx(version);
+        install(version.clone(), Some(&host), locations).await?;
+    }
+    Ok(())
+}
+
+fn should_auto_install(version: &Version, locations: &Locations) -> Result<bool, AutoInstallError> {
+    let install = match auto_install_mode(locations)? {
+        AutoInstall::Never => false,
+        AutoInstall::Always => true,
+        // without a terminal to ask, behave as if auto-install were off
+        AutoInstall::Prompt => std::io::stdin().is_terminal() && confirm_install(version)?,
+    };
+    Ok(install)
+}
+
+// DFXVM_AUTO_INSTALL takes precedence over the auto_install setting
+fn auto_install_mode(locations: &Locations) -> Result<AutoInstall, GetAutoInstallModeError> {
+    match std::env::var("DFXVM_AUTO_INSTALL") {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map_err(|()| GetAutoInstallModeError::ParseEnvironment(value)),
+        _ => Ok(Settings::load_or_default(&locations.settings_path())?.auto_install()),
+    }
+}
+
+fn confirm_install(version: &Version) -> Result<bool, InteractError> {
+    let install = Confirm::new()
+        .with_prompt(format!("dfx {version} is not installed. Install it now?"))
+        .default(true)
+        .interact()?;
+    Ok(install)
+}
+
 fn trying_to_call_dfx_upgrade(args: &[OsString]) -> bool {
     let mut iter = args.iter().peekable();
 
@@ -82,29 +172,34 @@ fn trying_to_call_dfx_upgrade(args: &[OsString]) -> bool {
     false
 }
 
-fn get_dfx_version_and_command_args<'args>(
+async fn get_dfx_version_and_command_args<'args>(
     args: &'args [OsString],
     locations: &Locations,
-) -> Result<Option<(Version, &'args [OsString])>, DetermineDfxVersionError> {
+) -> Result<Option<(Toolchain, &'args [OsString])>, DetermineDfxVersionError> {
+    if let Some(sourced) = get_version_from_program_name(&args[0], locations)? {
+        // a versioned proxy passes all arguments through, including any +<version>
+        let version = sourced.resolve(locations).await?;
+        return Ok(Some((version, &args[1..])));
+    }
+
     let args = &args[1..]; // skip the binary name
 
-    if let Some(version) = get_version_from_commandline(args)? {
-        Ok(Some((version, &args[1..]))) // skip the version parameter
-    } else if let Some(version) = get_version_from_environment()? {
-        Ok(Some((version, args)))
-    } else if let Some(version) = get_version_from_dfx_json()? {
-        Ok(Some((version, args)))
-    } else if let Some(version) = get_version_from_settings(locations)? {
-        Ok(Some((version, args)))
+    let commandline = get_version_from_commandline(args)?;
+    let args = if commandline.is_some() {
+        &args[1..] // skip the version parameter
     } else {
-        Ok(None)
-    }
+        args
+    };
+    let version = determine_dfx_version(commandline, locations)
+        .await?
+        .map(|(version, _source)| (version, args));
+    Ok(version)
 }
 
-// the first argument may be a version (starts with "+"),
+// the first argument may be a version or tag (starts with "+"),
 fn get_version_from_commandline(
     args: &[OsString],
-) -> Result<Option<Version>, GetVersionFromCommandLineError> {
+) -> Result<Option<VersionOrTag>, GetVersionFromCommandLineError> {
     let version = args.first();
     let version = version
         .map(|s| std::str::from_utf8(s.as_bytes()))
@@ -112,54 +207,14 @@ fn get_version_from_commandline(
         .filter(|&s| s.starts_with('+'))
         .map(|s| {
             let v = &s[1..];
-            v.parse::<Version>()
-                .map_err(|source| GetVersionFromCommandLineError::ParseVersion {
+            v.parse::<VersionOrTag>().map_err(|source| {
+                GetVersionFromCommandLineError::ParseVersion {
                     version: v.to_string(),
                     source,
-                })
+                }
+            })
         })
         .transpose()?;
 
     Ok(version)
 }
-
-fn get_version_from_environment() -> Result<Option<Version>, GetVersionFromEnvironmentError> {
-    std::env::var("DFX_VERSION")
-        .ok()
-        .filter(|s| !s.trim().is_empty())
-        .map(|version| {
-            Version::parse(&version)
-                .map_err(|source| GetVersionFromEnvironmentError { version, source })
-        })
-        .transpose()
-}
-
-#[derive(Clone, Debug, Deserialize)]
-pub struct DfxJson {
-    pub dfx: Option<Version>,
-}
-
-fn get_version_from_dfx_json() -> Result<Option<Version>, GetVersionFromDfxJsonError> {
-    let dfx_json_path: Option<PathBuf> = find_dfx_json()?;
-    let Some(dfx_json_path) = dfx_json_path else {
-        return Ok(None);
-    };
-    let dfx_json: DfxJson = load_json_file(&dfx_json_path)?;
-
-    Ok(dfx_json.dfx)
-}
-
-fn find_dfx_json() -> Result<Option<PathBuf>, FindDfxJsonError> {
-    for parent in crate::fs::canonicalize(&crate::env::current_dir()?)?.ancestors() {
-        let path = parent.join("dfx.json");
-        if path.exists() {
-            return Ok(Some(path));
-        }
-    }
-    Ok(None)
-}
-
-fn get_version_from_settings(locations: &Locations) -> Result<Option<Version>, LoadJsonFileError> {
-    let path = locations.settings_path();
-    Settings::load_or_default(&path).map(|settings| settings.default_version)
-}
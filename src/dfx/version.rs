@@ -0,0 +1,542 @@
+use crate::dfxvm::{fetch_manifest, installed_versions, manifest_urls, resolve_tag};
+use crate::error::dfx::{
+    DetermineDfxVersionError, FindDfxJsonError, GetVersionFromDfxJsonError,
+    GetVersionFromEnvironmentError, GetVersionFromOverrideError, GetVersionFromProgramNameError,
+    GetVersionFromProgramNameError::ParseVersion,
+    GetVersionFromToolchainFileError, ResolveVersionReqError,
+    ResolveVersionReqError::{FetchManifest, NoMatchingVersionInstalled},
+    ResolveVersionSpecError,
+};
+use crate::error::dfxvm::manifest::ResolveTagError;
+use crate::error::json::LoadJsonFileError;
+use crate::fs::read_to_string;
+use crate::json::load_json_file;
+use crate::locations::Locations;
+use crate::settings::Settings;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const SHIM_PREFIX: &str = "dfx-";
+const DFX_VERSION_FILENAME: &str = ".dfx-version";
+const TOOL_VERSIONS_FILENAME: &str = ".tool-versions";
+
+// Where a dfx version came from, listed in order of precedence
+#[derive(Clone, Debug)]
+pub enum VersionSource {
+    CommandLine,
+    Environment,
+    ProgramName { name: String },
+    Override { dir: PathBuf },
+    DfxJson { path: PathBuf },
+    ToolchainFile { path: PathBuf },
+    Settings { path: PathBuf },
+}
+
+impl VersionSource {
+    // The file or directory that specified the version, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            VersionSource::CommandLine
+            | VersionSource::ProgramName { .. }
+            | VersionSource::Environment => None,
+            VersionSource::Override { dir } => Some(dir),
+            VersionSource::DfxJson { path }
+            | VersionSource::ToolchainFile { path }
+            | VersionSource::Settings { path } => Some(path),
+        }
+    }
+}
+
+impl fmt::Display for VersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionSource::CommandLine => write!(f, "command line"),
+            VersionSource::ProgramName { name } => write!(f, "program name {name}"),
+            VersionSource::Environment => write!(f, "DFX_VERSION environment variable"),
+            VersionSource::Override { dir } => write!(f, "override for {}", dir.display()),
+            VersionSource::DfxJson { path } | VersionSource::ToolchainFile { path } => {
+                write!(f, "{}", path.display())
+            }
+            VersionSource::Settings { path } => write!(f, "default_version in {}", path.display()),
+        }
+    }
+}
+
+// An exact version ("0.24.1"), a semver requirement ("^0.24", ">=0.23, <0.25"),
+// or a tag from the manifest ("latest").
+#[derive(Clone, Debug)]
+pub enum DfxVersionSpec {
+    Exact(Version),
+    Requirement(VersionReq),
+    Tag(String),
+}
+
+impl FromStr for DfxVersionSpec {
+    type Err = semver::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(version) = Version::parse(s) {
+            return Ok(DfxVersionSpec::Exact(version));
+        }
+        match VersionReq::parse(s) {
+            Ok(req) => Ok(DfxVersionSpec::Requirement(req)),
+            Err(_) if is_tag_name(s) => Ok(DfxVersionSpec::Tag(s.to_string())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DfxVersionSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for DfxVersionSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for DfxVersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DfxVersionSpec::Exact(version) => write!(f, "{version}"),
+            DfxVersionSpec::Requirement(req) => write!(f, "{req}"),
+            DfxVersionSpec::Tag(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+// An exact version, or a tag from the manifest.  Accepted wherever a single
+// version is named: dfx +<version>, DFX_VERSION, and dfxvm commands.
+#[derive(Clone, Debug)]
+pub enum VersionOrTag {
+    Version(Version),
+    Tag(String),
+}
+
+impl VersionOrTag {
+    pub async fn resolve(&self, locations: &Locations) -> Result<Toolchain, ResolveTagError> {
+        match self {
+            VersionOrTag::Version(version) => Ok(Toolchain::Release(version.clone())),
+            VersionOrTag::Tag(tag) => Toolchain::from_tag(tag, locations).await,
+        }
+    }
+}
+
+impl FromStr for VersionOrTag {
+    type Err = semver::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Version::parse(s) {
+            Ok(version) => Ok(VersionOrTag::Version(version)),
+            Err(_) if is_tag_name(s) => Ok(VersionOrTag::Tag(s.to_string())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl fmt::Display for VersionOrTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionOrTag::Version(version) => write!(f, "{version}"),
+            VersionOrTag::Tag(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+impl From<VersionOrTag> for DfxVersionSpec {
+    fn from(version: VersionOrTag) -> Self {
+        match version {
+            VersionOrTag::Version(version) => DfxVersionSpec::Exact(version),
+            VersionOrTag::Tag(tag) => DfxVersionSpec::Tag(tag),
+        }
+    }
+}
+
+// What the proxy runs: an installed release, or a dfx linked with `dfxvm link`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Toolchain {
+    Release(Version),
+    Custom(String),
+}
+
+impl Toolchain {
+    // A linked toolchain takes precedence over a manifest tag of the same name.
+    async fn from_tag(tag: &str, locations: &Locations) -> Result<Self, ResolveTagError> {
+        let custom = Toolchain::Custom(tag.to_string());
+        if is_toolchain_name(tag) && locations.toolchain_dir(&custom).is_dir() {
+            Ok(custom)
+        } else {
+            resolve_tag(tag, locations).await.map(Toolchain::Release)
+        }
+    }
+}
+
+impl From<Version> for Toolchain {
+    fn from(version: Version) -> Self {
+        Toolchain::Release(version)
+    }
+}
+
+impl From<Toolchain> for DfxVersionSpec {
+    fn from(toolchain: Toolchain) -> Self {
+        match toolchain {
+            Toolchain::Release(version) => DfxVersionSpec::Exact(version),
+            Toolchain::Custom(name) => DfxVersionSpec::Tag(name),
+        }
+    }
+}
+
+impl FromStr for Toolchain {
+    type Err = semver::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Version::parse(s) {
+            Ok(version) => Ok(Toolchain::Release(version)),
+            Err(_) if is_toolchain_name(s) => Ok(Toolchain::Custom(s.to_string())),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Toolchain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Toolchain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Toolchain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Toolchain::Release(version) => write!(f, "{version}"),
+            Toolchain::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+// Names for linked toolchains are tag names without dots,
+// so that a malformed version such as "X.2" is still rejected.
+pub fn is_toolchain_name(s: &str) -> bool {
+    is_tag_name(s) && !s.contains('.')
+}
+
+// Tags start with a letter, so they can't be mistaken for versions.
+fn is_tag_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+#[derive(Clone, Debug)]
+pub struct SourcedVersion {
+    pub spec: DfxVersionSpec,
+    pub source: VersionSource,
+}
+
+impl SourcedVersion {
+    fn exact(toolchain: impl Into<Toolchain>, source: VersionSource) -> Self {
+        Self {
+            spec: toolchain.into().into(),
+            source,
+        }
+    }
+
+    // Picks the highest installed version that matches a requirement,
+    // or looks up the linked toolchain or version for a tag.
+    pub async fn resolve(
+        &self,
+        locations: &Locations,
+    ) -> Result<Toolchain, ResolveVersionSpecError> {
+        let toolchain = match &self.spec {
+            DfxVersionSpec::Exact(version) => Toolchain::Release(version.clone()),
+            DfxVersionSpec::Requirement(req) => {
+                Toolchain::Release(resolve_version_req(req, &self.source, locations).await?)
+            }
+            DfxVersionSpec::Tag(tag) => Toolchain::from_tag(tag, locations).await?,
+        };
+        Ok(toolchain)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DfxJson {
+    pub dfx: Option<DfxVersionSpec>,
+}
+
+// Determines the dfx version to use, given a version from the command line if any.
+pub async fn determine_dfx_version(
+    commandline: Option<VersionOrTag>,
+    locations: &Locations,
+) -> Result<Option<(Toolchain, VersionSource)>, DetermineDfxVersionError> {
+    let sourced = match commandline {
+        Some(version) => Some(SourcedVersion {
+            spec: version.into(),
+            source: VersionSource::CommandLine,
+        }),
+        None => find_version(locations).await?,
+    };
+    let Some(sourced) = sourced else {
+        return Ok(None);
+    };
+    let version = sourced.resolve(locations).await?;
+    Ok(Some((version, sourced.source)))
+}
+
+async fn find_version(
+    locations: &Locations,
+) -> Result<Option<SourcedVersion>, DetermineDfxVersionError> {
+    if let Some(sourced) = get_version_from_environment()? {
+        Ok(Some(sourced))
+    } else if let Some(sourced) = get_version_from_override(locations)? {
+        Ok(Some(sourced))
+    } else if let Some(sourced) = get_version_from_project_files()? {
+        Ok(Some(sourced))
+    } else if let Some(sourced) = get_version_from_settings(locations)? {
+        Ok(Some(sourced))
+    } else {
+        Ok(None)
+    }
+}
+
+// dfx.json, .dfx-version or .tool-versions, whichever is in the directory nearest
+// the current directory.  In the same directory, dfx.json comes first.
+fn get_version_from_project_files() -> Result<Option<SourcedVersion>, DetermineDfxVersionError> {
+    let dfx_json = get_version_from_dfx_json()?;
+    let toolchain_file = get_version_from_toolchain_file()?;
+    let sourced = match (dfx_json, toolchain_file) {
+        (Some(dfx_json), Some(toolchain_file))
+            if is_nearer(&toolchain_file.source, &dfx_json.source) =>
+        {
+            Some(toolchain_file)
+        }
+        (dfx_json, toolchain_file) => dfx_json.or(toolchain_file),
+    };
+    Ok(sourced)
+}
+
+// Both files are in the current directory or one of its parents.
+fn is_nearer(a: &VersionSource, b: &VersionSource) -> bool {
+    match (
+        a.path().and_then(Path::parent),
+        b.path().and_then(Path::parent),
+    ) {
+        (Some(a), Some(b)) => a != b && a.starts_with(b),
+        _ => false,
+    }
+}
+
+// A proxy named dfx-<something> runs the version recorded for it by `dfxvm shim add`,
+// or else the version, requirement or tag that <something> names.
+pub fn get_version_from_program_name(
+    program: &OsStr,
+    locations: &Locations,
+) -> Result<Option<SourcedVersion>, GetVersionFromProgramNameError> {
+    let Some(name) = Path::new(program).file_name().and_then(OsStr::to_str) else {
+        return Ok(None);
+    };
+    let Some(suffix) = name.strip_prefix(SHIM_PREFIX) else {
+        return Ok(None);
+    };
+
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let spec = match settings.shims.get(name) {
+        Some(spec) => spec.clone(),
+        None => suffix.parse().map_err(|source| ParseVersion {
+            name: name.to_string(),
+            source,
+        })?,
+    };
+    let source = VersionSource::ProgramName {
+        name: name.to_string(),
+    };
+    Ok(Some(SourcedVersion { spec, source }))
+}
+
+pub fn get_version_from_environment(
+) -> Result<Option<SourcedVersion>, GetVersionFromEnvironmentError> {
+    std::env::var("DFX_VERSION")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .map(|version| {
+            version
+                .parse::<VersionOrTag>()
+                .map(|spec| SourcedVersion {
+                    spec: spec.into(),
+                    source: VersionSource::Environment,
+                })
+                .map_err(|source| GetVersionFromEnvironmentError { version, source })
+        })
+        .transpose()
+}
+
+// set with `dfxvm override set`, for the current directory or its closest ancestor
+pub fn get_version_from_override(
+    locations: &Locations,
+) -> Result<Option<SourcedVersion>, GetVersionFromOverrideError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    if settings.overrides.is_empty() {
+        return Ok(None);
+    }
+    let current_dir = crate::fs::canonicalize(&crate::env::current_dir()?)?;
+    let sourced = settings.override_for(&current_dir).map(|(dir, version)| {
+        let source = VersionSource::Override {
+            dir: dir.to_path_buf(),
+        };
+        SourcedVersion::exact(version.clone(), source)
+    });
+    Ok(sourced)
+}
+
+pub fn get_version_from_dfx_json() -> Result<Option<SourcedVersion>, GetVersionFromDfxJsonError> {
+    let dfx_json_path: Option<PathBuf> = find_dfx_json()?;
+    let Some(dfx_json_path) = dfx_json_path else {
+        return Ok(None);
+    };
+    let dfx_json: DfxJson = load_json_file(&dfx_json_path)?;
+
+    let sourced = dfx_json.dfx.map(|spec| SourcedVersion {
+        spec,
+        source: VersionSource::DfxJson {
+            path: dfx_json_path,
+        },
+    });
+    Ok(sourced)
+}
+
+pub fn find_dfx_json() -> Result<Option<PathBuf>, FindDfxJsonError> {
+    for parent in crate::fs::canonicalize(&crate::env::current_dir()?)?.ancestors() {
+        let path = parent.join("dfx.json");
+        if path.exists() {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+// Looks for a .dfx-version file, or a .tool-versions file with a dfx line,
+// in the current directory and then in parent directories.
+pub fn get_version_from_toolchain_file(
+) -> Result<Option<SourcedVersion>, GetVersionFromToolchainFileError> {
+    for parent in crate::fs::canonicalize(&crate::env::current_dir()?)?.ancestors() {
+        let path = parent.join(DFX_VERSION_FILENAME);
+        if path.exists() {
+            if let Some(spec) = read_dfx_version_file(&path)? {
+                let source = VersionSource::ToolchainFile { path };
+                return Ok(Some(SourcedVersion { spec, source }));
+            }
+        }
+        let path = parent.join(TOOL_VERSIONS_FILENAME);
+        if path.exists() {
+            if let Some(spec) = read_tool_versions_file(&path)? {
+                let source = VersionSource::ToolchainFile { path };
+                return Ok(Some(SourcedVersion { spec, source }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// The whole file is the version, ignoring surrounding whitespace.
+fn read_dfx_version_file(
+    path: &Path,
+) -> Result<Option<DfxVersionSpec>, GetVersionFromToolchainFileError> {
+    let contents = read_to_string(path)?;
+    let version = contents.trim();
+    if version.is_empty() {
+        return Ok(None);
+    }
+    parse_toolchain_file_version(version, path).map(Some)
+}
+
+// asdf format: one "<tool> <version> [<fallback version>...]" per line, with # comments.
+// Only the first version on the dfx line is used.
+fn read_tool_versions_file(
+    path: &Path,
+) -> Result<Option<DfxVersionSpec>, GetVersionFromToolchainFileError> {
+    let contents = read_to_string(path)?;
+    let version = contents.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        (words.next() == Some("dfx"))
+            .then(|| words.next())
+            .flatten()
+    });
+    version
+        .map(|version| parse_toolchain_file_version(version, path))
+        .transpose()
+}
+
+fn parse_toolchain_file_version(
+    version: &str,
+    path: &Path,
+) -> Result<DfxVersionSpec, GetVersionFromToolchainFileError> {
+    version
+        .parse()
+        .map_err(|source| GetVersionFromToolchainFileError::ParseVersion {
+            version: version.to_string(),
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+pub fn get_version_from_settings(
+    locations: &Locations,
+) -> Result<Option<SourcedVersion>, LoadJsonFileError> {
+    let path = locations.settings_path();
+    let settings = Settings::load_or_default(&path)?;
+    let sourced = settings
+        .default_version
+        .map(|toolchain| SourcedVersion::exact(toolchain, VersionSource::Settings { path }));
+    Ok(sourced)
+}
+
+// Picks the highest installed version that matches.  If none match,
+// looks up which released versions would, so the error can suggest them.
+async fn resolve_version_req(
+    req: &VersionReq,
+    source: &VersionSource,
+    locations: &Locations,
+) -> Result<Version, ResolveVersionReqError> {
+    if let Some(version) = installed_versions(locations)?
+        .into_iter()
+        .filter(|v| req.matches(v))
+        .max()
+    {
+        return Ok(version);
+    }
+
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let urls = manifest_urls(&settings)?;
+    let manifest = fetch_manifest(&urls, &settings, locations)
+        .await
+        .map_err(|source| FetchManifest {
+            requirement: req.clone(),
+            source,
+        })?;
+    let mut available: Vec<Version> = manifest
+        .versions
+        .into_iter()
+        .filter(|v| req.matches(v))
+        .collect();
+    available.sort_by(|a, b| b.cmp(a));
+
+    Err(NoMatchingVersionInstalled {
+        requirement: req.clone(),
+        from: source.to_string(),
+        available,
+    })
+}
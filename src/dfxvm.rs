@@ -1,15 +1,27 @@
+mod cache;
 mod cli;
 mod default;
 mod install;
+mod journal;
+mod link;
 mod list;
+mod lock;
 mod manifest;
+mod overrides;
+mod run;
 mod self_uninstall;
 mod self_update;
+mod shim;
+mod show;
 mod uninstall;
 mod update;
+mod which;
 
 pub use cli::main;
 pub use default::set_default;
+pub use install::{install, installed_target, installed_versions};
+pub use journal::recover_interrupted_operations;
+pub use manifest::{fetch_manifest, manifest_urls, resolve_tag};
 pub use self_update::cleanup_self_updater;
 pub use self_update::self_replace;
 pub use update::update;
@@ -0,0 +1,197 @@
+use crate::dfxvm::cli::{CacheCommand, CacheOpts};
+use crate::download::hash_file;
+use crate::error::{
+    dfxvm::cache::{AddToCacheError, CacheError, PruneCacheError},
+    fs::{ReadDirError, RemoveDirAllError},
+};
+use crate::fs::{copy, create_dir_all, read_dir, remove_dir_all};
+use crate::json::{load_json_file, save_json_file};
+use crate::locations::Locations;
+use crate::settings::Settings;
+use indicatif::HumanBytes;
+use serde::{Deserialize, Serialize};
+use std::fs::hard_link;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+const ENTRY_FILENAME: &str = "entry.json";
+
+// Each cached download is a directory in the download cache, named for the
+// sha256 of its contents, that holds the file and an entry.json describing it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    url: String,
+    filename: String,
+    size: u64,
+    last_used: u64, // seconds since the unix epoch
+}
+
+struct CachedDownload {
+    dir: PathBuf,
+    sha256: String,
+    entry: CacheEntry,
+}
+
+pub fn cache(opts: CacheOpts, locations: &Locations) -> Result<(), CacheError> {
+    match opts.command {
+        CacheCommand::Clear(_opts) => clear_cache(locations)?,
+        CacheCommand::List(_opts) => list_cache(locations)?,
+        CacheCommand::Prune(_opts) => prune_cache(locations)?,
+    }
+    Ok(())
+}
+
+// A cached download with the given sha256, if its contents still match,
+// linked into dest_dir so that it stays in place for the caller even if
+// another process prunes it from the cache.
+// A cached download that doesn't match is removed.
+pub fn find_cached_download(
+    sha256: &str,
+    dest_dir: &Path,
+    locations: &Locations,
+) -> Option<PathBuf> {
+    let dir = locations.download_cache_dir().join(sha256);
+    let entry_path = dir.join(ENTRY_FILENAME);
+    if !entry_path.exists() {
+        return None;
+    }
+    let mut entry = load_json_file::<CacheEntry>(&entry_path).ok()?;
+    let path = dir.join(&entry.filename);
+    let linked_path = dest_dir.join(&entry.filename);
+    let _ = std::fs::remove_file(&linked_path);
+    if hard_link(&path, &linked_path).is_err() {
+        copy(&path, &linked_path).ok()?;
+    }
+    match hash_file(&linked_path) {
+        Ok(hash) if hash.as_str() == sha256 => {
+            entry.last_used = now();
+            let _ = save_json_file(&entry_path, &entry);
+            Some(linked_path)
+        }
+        _ => {
+            info!("removing corrupt cached download {}", path.display());
+            let _ = remove_dir_all(&dir);
+            let _ = std::fs::remove_file(&linked_path);
+            None
+        }
+    }
+}
+
+// Adds a verified download to the cache.  The download is linked rather than
+// moved, so that it stays in place for the caller even if another process
+// prunes it from the cache.
+pub fn add_to_cache(
+    path: &Path,
+    sha256: &str,
+    url: &Url,
+    locations: &Locations,
+) -> Result<(), AddToCacheError> {
+    let filename = path
+        .file_name()
+        .expect("downloaded file must have a filename")
+        .to_string_lossy()
+        .to_string();
+    let size = crate::fs::metadata(path)?.len();
+
+    let dir = locations.download_cache_dir().join(sha256);
+    create_dir_all(&dir)?;
+    let cached_path = dir.join(&filename);
+    // another process may have just cached the same file
+    if !cached_path.exists() && hard_link(path, &cached_path).is_err() {
+        copy(path, &cached_path)?;
+    }
+
+    let entry = CacheEntry {
+        url: url.to_string(),
+        filename,
+        size,
+        last_used: now(),
+    };
+    save_json_file(&dir.join(ENTRY_FILENAME), &entry)?;
+    Ok(())
+}
+
+// Removes the least recently used downloads until the cache fits in its size limit.
+pub fn prune_cache(locations: &Locations) -> Result<(), PruneCacheError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let limit = settings.download_cache_limit_bytes();
+
+    let mut downloads = cached_downloads(locations)?;
+    downloads.sort_by_key(|download| download.entry.last_used);
+
+    let mut total: u64 = downloads.iter().map(|d| d.entry.size).sum();
+    let mut removed = 0;
+    let mut removed_size = 0;
+    for download in downloads {
+        if total <= limit {
+            break;
+        }
+        remove_dir_all(&download.dir)?;
+        total -= download.entry.size;
+        removed += 1;
+        removed_size += download.entry.size;
+    }
+
+    if removed > 0 {
+        info!(
+            "removed {removed} cached download(s) ({}) to fit the {} limit",
+            HumanBytes(removed_size),
+            HumanBytes(limit)
+        );
+    }
+    Ok(())
+}
+
+fn list_cache(locations: &Locations) -> Result<(), ReadDirError> {
+    let mut downloads = cached_downloads(locations)?;
+    downloads.sort_by_key(|download| std::cmp::Reverse(download.entry.last_used));
+
+    for download in downloads {
+        println!(
+            "{}  {:>10}  {}",
+            &download.sha256[..12],
+            HumanBytes(download.entry.size).to_string(),
+            download.entry.url
+        );
+    }
+    Ok(())
+}
+
+fn clear_cache(locations: &Locations) -> Result<(), RemoveDirAllError> {
+    let dir = locations.download_cache_dir();
+    if dir.exists() {
+        remove_dir_all(&dir)?;
+    }
+    info!("cleared the download cache");
+    Ok(())
+}
+
+// Skips partial downloads, which are in directories whose names start with '.'
+fn cached_downloads(locations: &Locations) -> Result<Vec<CachedDownload>, ReadDirError> {
+    let cache_dir = locations.download_cache_dir();
+    if !cache_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let downloads = read_dir(&cache_dir)?
+        .filter_map(Result::ok)
+        .filter_map(|dir_entry| {
+            let sha256 = dir_entry.file_name().to_str()?.to_string();
+            if sha256.starts_with('.') {
+                return None;
+            }
+            let dir = dir_entry.path();
+            let entry = load_json_file::<CacheEntry>(&dir.join(ENTRY_FILENAME)).ok()?;
+            Some(CachedDownload { dir, sha256, entry })
+        })
+        .collect();
+    Ok(downloads)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,45 +1,143 @@
+use crate::dfx::{DfxVersionSpec, VersionOrTag};
 use crate::dfxvm::{
-    cleanup_self_updater, default::default, install::install, list::list,
-    self_uninstall::self_uninstall, self_update::self_update, uninstall::uninstall, update::update,
+    cache::cache,
+    cleanup_self_updater,
+    default::default,
+    install::{install_from_file, install_versions},
+    link::link,
+    list::list,
+    lock::lock,
+    manifest::resolve_release,
+    overrides::overrides,
+    recover_interrupted_operations,
+    run::run,
+    self_uninstall::self_uninstall,
+    self_update::self_update,
+    shim::shim,
+    show::show,
+    uninstall::uninstall,
+    update::update,
+    which::which,
 };
 use crate::error::dfxvm;
 use crate::locations::Locations;
-use clap::{Parser, Subcommand};
+use crate::retry::override_retries;
+use crate::target::Target;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 use semver::Version;
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 /// The dfx version manager
 #[derive(Parser)]
 #[command(name = "dfxvm", arg_required_else_help = true, version)]
 pub struct Cli {
+    /// Retry a failed request at most this many times.
+    /// Defaults to the retries setting, or 3
+    #[arg(long, global = true, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Fail as soon as a request fails, rather than retrying it
+    #[arg(long, global = true, conflicts_with = "retries")]
+    no_retry: bool,
+
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Subcommand)]
 pub enum Command {
+    Cache(CacheOpts),
     Default(DefaultOpts),
     Install(InstallOpts),
+    Link(LinkOpts),
     List(ListOpts),
+    Lock(LockOpts),
+    #[command(name = "override")]
+    Override(OverrideOpts),
+    Run(RunOpts),
     #[command(name = "self")]
     SelfCmd(SelfOpts),
+    Shim(ShimOpts),
+    Show(ShowOpts),
     Uninstall(UninstallOpts),
     Update(UpdateOpts),
+    Which(WhichOpts),
 }
 
-/// Install a version of dfx
+/// Install one or more versions of dfx
 #[derive(Parser)]
 pub struct InstallOpts {
-    /// dfx version to install
-    version: Version,
+    /// dfx versions, partial versions (such as "0.24"), or tags (such as "latest") to install
+    #[arg(required = true)]
+    versions: Vec<DfxVersionSpec>,
+
+    /// Install from a local release tarball rather than downloading it.
+    /// Requires a single exact version, such as 0.24.1
+    #[arg(long, value_name = "PATH", requires = "versions")]
+    from_file: Option<PathBuf>,
+
+    /// Expected sha256 of the tarball given with --from-file.
+    /// Defaults to the contents of <PATH>.sha256
+    #[arg(long, value_name = "HEX", requires = "from_file")]
+    sha256: Option<String>,
+
+    /// Install releases built for this target triple, such as
+    /// aarch64-unknown-linux-gnu, rather than for this machine
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<Target>,
+
+    /// Run `dfx cache install` for each version after installing it,
+    /// so that dfx doesn't unpack its cache on first use
+    #[arg(long)]
+    warm_cache: bool,
+}
+
+/// Manage the cache of downloaded dfx release tarballs
+#[derive(Parser)]
+#[command(arg_required_else_help = true)]
+pub struct CacheOpts {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    Clear(CacheClearOpts),
+    List(CacheListOpts),
+    Prune(CachePruneOpts),
 }
 
+/// Remove all cached downloads
+#[derive(Parser)]
+pub struct CacheClearOpts {}
+
+/// List cached downloads, most recently used first
+#[derive(Parser)]
+pub struct CacheListOpts {}
+
+/// Remove the least recently used downloads until the cache fits in its size limit
+#[derive(Parser)]
+pub struct CachePruneOpts {}
+
 /// Set a dfx version to be the default, installing if necessary
 #[derive(Parser)]
 pub struct DefaultOpts {
-    /// dfx version to use by default
-    version: Option<Version>,
+    /// dfx version, partial version (such as "0.24"), tag (such as "latest"),
+    /// or name of a linked toolchain to use by default
+    version: Option<DfxVersionSpec>,
+}
+
+/// Link a locally built dfx as a custom toolchain
+#[derive(Parser)]
+pub struct LinkOpts {
+    /// Name of the toolchain, such as "dev"
+    name: String,
+
+    /// The dfx binary, or a directory that contains it
+    path: PathBuf,
 }
 
 /// List installed or available versions of dfx
@@ -54,17 +152,135 @@ pub struct ListOpts {
     pub limit: usize,
 }
 
+/// Record the checksums of dfx releases in dfxvm.lock, next to dfx.json
+#[derive(Parser)]
+pub struct LockOpts {
+    /// dfx versions, partial versions (such as "0.24"), or tags (such as "latest") to lock.
+    /// Defaults to the versions already in dfxvm.lock and the version in dfx.json
+    versions: Vec<DfxVersionSpec>,
+
+    /// Lock releases built for this target triple.  May be repeated.
+    /// Defaults to the targets already locked, and the target that `dfxvm install` uses
+    #[arg(long = "target", value_name = "TRIPLE")]
+    targets: Vec<Target>,
+}
+
+/// Modify directory dfx version overrides
+#[derive(Parser)]
+#[command(arg_required_else_help = true)]
+pub struct OverrideOpts {
+    #[command(subcommand)]
+    pub command: OverrideCommand,
+}
+
+#[derive(Subcommand)]
+pub enum OverrideCommand {
+    List(OverrideListOpts),
+    Set(OverrideSetOpts),
+    Unset(OverrideUnsetOpts),
+}
+
+/// List directory dfx version overrides
+#[derive(Parser)]
+pub struct OverrideListOpts {}
+
+/// Set the dfx version to use in a directory, installing if necessary
+#[derive(Parser)]
+pub struct OverrideSetOpts {
+    /// dfx version, partial version (such as "0.24"), or tag (such as "latest") to use in the directory
+    pub version: DfxVersionSpec,
+
+    /// Path to the directory.  Defaults to the current directory.
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+}
+
+/// Remove the dfx version override for a directory
+#[derive(Parser)]
+pub struct OverrideUnsetOpts {
+    /// Path to the directory.  Defaults to the current directory.
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+
+    /// Remove overrides for all directories that no longer exist.
+    #[arg(long, conflicts_with = "path")]
+    pub nonexistent: bool,
+}
+
+/// Run a command with a dfx version first on PATH and in DFX_VERSION
+#[derive(Parser)]
+pub struct RunOpts {
+    /// dfx version or tag (such as "latest") to run the command with
+    version: VersionOrTag,
+
+    /// The command to run, and its arguments
+    #[arg(required = true, last = true)]
+    command: Vec<OsString>,
+}
+
+/// Manage versioned dfx proxies, such as dfx-0.24.1
+#[derive(Parser)]
+#[command(arg_required_else_help = true)]
+pub struct ShimOpts {
+    #[command(subcommand)]
+    pub command: ShimCommand,
+}
+
+#[derive(Subcommand)]
+pub enum ShimCommand {
+    Add(ShimAddOpts),
+    List(ShimListOpts),
+    Remove(ShimRemoveOpts),
+}
+
+/// Add a dfx proxy that always runs a particular dfx version
+#[derive(Parser)]
+pub struct ShimAddOpts {
+    /// Name of the proxy, which must start with "dfx-"
+    pub name: String,
+
+    /// dfx version, version requirement, or tag (such as "latest") for the proxy to run
+    pub version: DfxVersionSpec,
+}
+
+/// List versioned dfx proxies
+#[derive(Parser)]
+pub struct ShimListOpts {}
+
+/// Remove a versioned dfx proxy
+#[derive(Parser)]
+pub struct ShimRemoveOpts {
+    /// Name of the proxy
+    pub name: String,
+}
+
+/// Show which dfx version would be used in the current directory, and why
+#[derive(Parser)]
+pub struct ShowOpts {
+    /// Behave as if this version or tag were passed as +<version> on the dfx command line
+    #[arg(long)]
+    version: Option<VersionOrTag>,
+}
+
 /// Uninstall a version of dfx
 #[derive(Parser)]
 pub struct UninstallOpts {
-    /// dfx version to uninstall
-    version: Version,
+    /// dfx version, tag, or name of a linked toolchain, to uninstall
+    version: VersionOrTag,
 }
 
 /// Update to latest version of dfx
 #[derive(Parser)]
 pub struct UpdateOpts {}
 
+/// Display the path to the dfx binary that would be run in the current directory
+#[derive(Parser)]
+pub struct WhichOpts {
+    /// Display the path for this dfx version or tag, as if passed as +<version> on the dfx command line
+    #[arg(long)]
+    version: Option<VersionOrTag>,
+}
+
 /// Manage dfxvm itself
 #[derive(Parser)]
 #[command(arg_required_else_help = true)]
@@ -91,19 +307,74 @@ pub struct SelfUninstallOpts {
     yes: bool,
 }
 
+// clap can't limit the number of versions only when --from-file is given,
+// so this reports a usage error as clap would.
+fn from_file_version(versions: &[DfxVersionSpec]) -> Version {
+    if let [DfxVersionSpec::Exact(version)] = versions {
+        return version.clone();
+    }
+    let mut command = Cli::command();
+    command.build();
+    command
+        .find_subcommand_mut("install")
+        .expect("install subcommand")
+        .error(
+            ErrorKind::ValueValidation,
+            "--from-file requires exactly one version, which must be exact, such as 0.24.1",
+        )
+        .exit()
+}
+
 pub async fn main(args: &[OsString], locations: &Locations) -> Result<ExitCode, dfxvm::Error> {
     cleanup_self_updater(locations)?;
+    recover_interrupted_operations(locations);
     let cli = Cli::parse_from(args);
+    if cli.no_retry {
+        override_retries(0);
+    } else if let Some(retries) = cli.retries {
+        override_retries(retries);
+    }
     match cli.command {
+        Command::Cache(opts) => cache(opts, locations)?,
         Command::Default(opts) => default(opts.version, locations).await?,
-        Command::Install(opts) => install(opts.version, locations).await?,
+        Command::Install(InstallOpts {
+            versions,
+            from_file: Some(tarball_path),
+            sha256,
+            target,
+            warm_cache,
+        }) => {
+            let version = from_file_version(&versions);
+            install_from_file(
+                &version,
+                &tarball_path,
+                sha256,
+                target,
+                warm_cache,
+                locations,
+            )?
+        }
+        Command::Install(opts) => {
+            let mut versions = vec![];
+            for spec in &opts.versions {
+                versions.push(resolve_release(spec, locations).await?);
+            }
+            install_versions(versions, opts.target, opts.warm_cache, locations).await?
+        }
+        Command::Link(opts) => link(&opts.name, &opts.path, locations)?,
         Command::List(opts) => list(opts, locations).await?,
+        Command::Lock(opts) => lock(opts.versions, opts.targets, locations).await?,
+        Command::Override(opts) => overrides(opts, locations).await?,
+        Command::Run(opts) => run(opts.version, opts.command, locations).await?,
         Command::SelfCmd(opts) => match opts.command {
             SelfCommand::Update(_opts) => self_update(locations).await?,
             SelfCommand::Uninstall(opts) => self_uninstall(opts.yes, locations)?,
         },
-        Command::Uninstall(opts) => uninstall(opts.version, locations)?,
+        Command::Shim(opts) => shim(opts, locations)?,
+        Command::Show(opts) => show(opts.version, locations).await?,
+        Command::Uninstall(opts) => uninstall(opts.version, locations).await?,
         Command::Update(_opts) => update(locations).await?,
+        Command::Which(opts) => which(opts.version, locations).await?,
     };
     Ok(ExitCode::SUCCESS)
 }
@@ -1,40 +1,57 @@
+use crate::dfx::{DfxVersionSpec, Toolchain};
 use crate::dfxvm::install::{install, installed};
+use crate::dfxvm::link::linked;
+use crate::dfxvm::manifest::resolve_release;
 use crate::error::dfxvm::default::{
     DefaultError, DisplayDefaultError, DisplayDefaultError::NoDefaultVersion, SetDefaultError,
 };
 use crate::fs::create_dir_all;
 use crate::locations::Locations;
+use crate::lock::FileLock;
 use crate::settings::Settings;
-use semver::Version;
 
-pub async fn default(version: Option<Version>, locations: &Locations) -> Result<(), DefaultError> {
+pub async fn default(
+    version: Option<DfxVersionSpec>,
+    locations: &Locations,
+) -> Result<(), DefaultError> {
     if let Some(version) = version {
-        set_default(&version, locations).await?;
+        let toolchain = match version {
+            // a linked toolchain takes precedence over a manifest tag of the same name
+            DfxVersionSpec::Tag(name) if linked(&name, locations) => Toolchain::Custom(name),
+            spec => Toolchain::Release(resolve_release(&spec, locations).await?),
+        };
+        set_default(&toolchain, locations).await?;
     } else {
         display_default(locations)?;
     }
     Ok(())
 }
 
-pub async fn set_default(version: &Version, locations: &Locations) -> Result<(), SetDefaultError> {
-    if installed(version, locations) {
-        info!("using existing install for dfx {version}");
-    } else {
-        install(version.clone(), locations).await?;
+pub async fn set_default(
+    toolchain: &Toolchain,
+    locations: &Locations,
+) -> Result<(), SetDefaultError> {
+    match toolchain {
+        Toolchain::Release(version) if installed(version, locations) => {
+            info!("using existing install for dfx {version}");
+        }
+        Toolchain::Release(version) => install(version.clone(), None, locations).await?,
+        Toolchain::Custom(name) => info!("using linked toolchain {name}"),
     }
 
+    let _lock = FileLock::acquire(&locations.settings_lock_path())?;
     let path = locations.settings_path();
     let mut settings = Settings::load_or_default(&path)?;
 
-    if matches!(settings.default_version, Some(ref v) if v == version) {
-        info!("dfx {} is already the default version", version);
+    if settings.default_version.as_ref() == Some(toolchain) {
+        info!("dfx {} is already the default version", toolchain);
     } else {
-        settings.default_version = Some(version.clone());
+        settings.default_version = Some(toolchain.clone());
 
         create_dir_all(locations.config_dir())?;
         settings.save(&path)?;
 
-        info!("set default version to dfx {}", version);
+        info!("set default version to dfx {}", toolchain);
     }
     Ok(())
 }
@@ -1,138 +1,758 @@
-use crate::download::{download_file, verify_checksum};
+use crate::archive::ArchiveFormat;
+use crate::dfx::Toolchain;
+use crate::dfxvm::cache::{add_to_cache, find_cached_download, prune_cache};
+use crate::dfxvm::journal::{JournalEntry, Operation};
+use crate::dfxvm::lock::{find_locked_release, LockedRelease};
+use crate::dfxvm::manifest::declared_archive_formats;
+use crate::download::{
+    download_file, download_signature, hash_file, read_checksum_file, verify_checksum, verify_hash,
+};
 use crate::error::{
     dfxvm::install::{
         ExtractArchiveError,
-        ExtractArchiveError::Unpack,
-        InstallError,
-        InstallError::{CreateTempDir, CreateTempDirIn},
+        ExtractArchiveError::{Decompress, UnknownFormat, Unpack},
+        InstallError, InstallFromFileError,
+        InstallFromFileError::{NoChecksum, NoSignature},
+        InstallTarballError, InstallVersionsError, InstalledForOtherTargetError, SmokeTestError,
+        SmokeTestError::{
+            NoDfxBinary, NotExecutable, ReadBinary, Run, TimedOut, VersionFailed, VersionMismatch,
+            VersionNotReported, WrongArchitecture,
+        },
+        WarmCacheError,
+        WarmCacheError::{CacheInstallFailed, Run as RunDfx},
     },
     download::{
         DownloadFileError, DownloadVerifiedTarballError,
-        DownloadVerifiedTarballError::{DownloadFile, NoSuchVersion},
+        DownloadVerifiedTarballError::{DownloadFile, LockfileMismatch, NoSuchVersion},
+        VerifyChecksumError::HashMismatch,
     },
-    reqwest::WrappedReqwestError,
+    fs::ReadDirError,
+    signature::VerifyFileSignatureError,
+};
+use crate::fs::{
+    create_dir_all, open_file, read_dir, remove_dir_all, rename, sync, sync_tree, write,
 };
-use crate::fs::{create_dir_all, open_file, rename};
+use crate::http;
+use crate::http::HttpClient;
 use crate::locations::Locations;
+use crate::lock::FileLock;
+use crate::log::log_error;
+use crate::mirror::try_mirrors;
 use crate::settings::Settings;
-use flate2::read::GzDecoder;
-use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::{Client, StatusCode, Url};
+use crate::signature::TrustedKeys;
+use crate::target::Target;
+use futures_util::future::join_all;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use itertools::Itertools;
+use reqwest::{StatusCode, Url};
 use semver::Version;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tar::Archive;
 
+const SMOKE_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Records the target that an installed version is built for
+const TARGET_FILENAME: &str = ".target";
+
 pub fn installed(version: &Version, locations: &Locations) -> bool {
     locations.version_dir(version).exists()
 }
 
-pub async fn install(version: Version, locations: &Locations) -> Result<(), InstallError> {
+// The target that an installed version is built for, if recorded.  Versions
+// installed before targets were recorded are built for this machine.
+pub fn installed_target(toolchain: &Toolchain, locations: &Locations) -> Option<Target> {
+    let path = locations.toolchain_dir(toolchain).join(TARGET_FILENAME);
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Fails if the version is installed for a different target than requested
+fn check_installed_target(
+    version: &Version,
+    requested: &Target,
+    locations: &Locations,
+) -> Result<(), InstalledForOtherTargetError> {
+    let installed = installed_target(&Toolchain::Release(version.clone()), locations)
+        .unwrap_or_else(|| Target::host_for_dfx(version));
+    if &installed != requested {
+        return Err(InstalledForOtherTargetError {
+            version: version.clone(),
+            installed,
+            requested: requested.clone(),
+        });
+    }
+    Ok(())
+}
+
+pub fn installed_versions(locations: &Locations) -> Result<Vec<Version>, ReadDirError> {
+    let versions_dir = locations.versions_dir();
+
+    if !versions_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let versions = read_dir(versions_dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|filename| Version::parse(filename).ok())
+        })
+        .sorted()
+        .collect();
+    Ok(versions)
+}
+
+// Installs for the target, or else the target from settings or this machine
+pub async fn install(
+    version: Version,
+    target: Option<&Target>,
+    locations: &Locations,
+) -> Result<(), InstallError> {
+    let newly_installed =
+        install_with_progress(version.clone(), target, locations, &MultiProgress::new()).await?;
+    prune_download_cache(locations);
+    if newly_installed && Settings::load_or_default(&locations.settings_path())?.warm_cache() {
+        warm_dfx_cache(&version, locations)?;
+    }
+    Ok(())
+}
+
+// Downloads concurrently, with a progress bar for each download.  Each version
+// is extracted into its own temporary directory and then renamed into place.
+pub async fn install_versions(
+    mut versions: Vec<Version>,
+    target: Option<Target>,
+    warm_cache: bool,
+    locations: &Locations,
+) -> Result<(), InstallVersionsError> {
+    versions.sort();
+    versions.dedup();
+    let warm_cache =
+        warm_cache || Settings::load_or_default(&locations.settings_path())?.warm_cache();
+
+    let progress = MultiProgress::new();
+    let results = join_all(versions.iter().map(|version| {
+        install_with_progress(version.clone(), target.as_ref(), locations, &progress)
+    }))
+    .await;
+    prune_download_cache(locations);
+
+    // with one version, its error is the command's error; with several,
+    // each failure is reported as it is found, and the error counts them
+    let requested = versions.len();
+    let mut failed = 0;
+    let mut installed = vec![];
+    for (version, result) in versions.into_iter().zip(results) {
+        match result {
+            Ok(true) => installed.push(version),
+            Ok(false) => {}
+            Err(e) if requested == 1 => {
+                return Err(InstallVersionsError::Install {
+                    version,
+                    source: Box::new(e),
+                })
+            }
+            Err(e) => {
+                err!("failed to install dfx {version}");
+                log_error(&e);
+                failed += 1;
+            }
+        }
+    }
+
+    // one at a time, after the downloads, so as not to hold them up
+    let mut not_warmed = 0;
+    if warm_cache {
+        for version in &installed {
+            match warm_dfx_cache(version, locations) {
+                Ok(()) => {}
+                Err(source) if requested == 1 => {
+                    return Err(InstallVersionsError::WarmCache {
+                        version: version.clone(),
+                        source,
+                    })
+                }
+                Err(e) => {
+                    err!("failed to warm the dfx cache for dfx {version}");
+                    log_error(&e);
+                    not_warmed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(InstallVersionsError::InstallSeveral { failed, requested });
+    }
+    if not_warmed > 0 {
+        return Err(InstallVersionsError::WarmCacheSeveral {
+            failed: not_warmed,
+            installed: installed.len(),
+        });
+    }
+    Ok(())
+}
+
+// Each install extracts a link to its tarball in its own download directory,
+// so pruning, here or in another process, can't remove a tarball in use.
+fn prune_download_cache(locations: &Locations) {
+    if let Err(e) = prune_cache(locations) {
+        info!("failed to prune the download cache");
+        log_error(&e);
+    }
+}
+
+// Installs for the target, or else the target from settings or this machine.
+// Returns whether the version was installed, rather than already installed.
+async fn install_with_progress(
+    version: Version,
+    target: Option<&Target>,
+    locations: &Locations,
+    progress: &MultiProgress,
+) -> Result<bool, InstallError> {
     let settings = Settings::load_or_default(&locations.settings_path())?;
-    let version_dir = locations.version_dir(&version);
+    let target = target.cloned().unwrap_or_else(|| settings.target(&version));
+    let toolchain = Toolchain::Release(version.clone());
+    let _lock =
+        FileLock::acquire_async(&locations.toolchain_lock_path(&toolchain), progress).await?;
     if installed(&version, locations) {
+        check_installed_target(&version, &target, locations)?;
+        progress.suspend(|| info!("dfx {version} is already installed"));
+        return Ok(false);
+    }
+    create_dir_all(locations.versions_dir())?;
+
+    progress.suspend(|| info!("installing dfx {version}"));
+
+    // download within the cache directory, so the tarball can be linked into the cache
+    let download_cache_dir = locations.download_cache_dir();
+    create_dir_all(&download_cache_dir)?;
+    let download_dir = tempfile::Builder::new()
+        .prefix(".download")
+        .tempdir_in(&download_cache_dir)
+        .map_err(|source| InstallError::CreateTempDirIn {
+            path: download_cache_dir,
+            source,
+        })?;
+
+    let downloaded_tarball_path = download_verified_tarball(
+        &version,
+        &target,
+        download_dir.path(),
+        &settings,
+        locations,
+        progress,
+    )
+    .await?;
+
+    // extracting and checking the binary would otherwise hold up the other downloads
+    let locations = locations.clone();
+    let progress = progress.clone();
+    unblock(move || {
+        install_tarball(
+            &version,
+            &target,
+            &downloaded_tarball_path,
+            &locations,
+            &progress,
+        )
+    })
+    .await?;
+
+    Ok(true)
+}
+
+// Runs blocking work on a thread of its own, so that the downloads running
+// alongside it on this thread keep going.
+async fn unblock<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+// Installs a release tarball that is already on disk, such as one copied from an
+// artifact store to a machine without network access.  The checksum comes from
+// the sha256 parameter, or else from a .sha256 file next to the tarball.
+pub fn install_from_file(
+    version: &Version,
+    tarball_path: &Path,
+    sha256: Option<String>,
+    target: Option<Target>,
+    warm_cache: bool,
+    locations: &Locations,
+) -> Result<(), InstallFromFileError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let target = target.unwrap_or_else(|| settings.target(version));
+    let _lock = FileLock::acquire(&locations.toolchain_lock_path(&version.clone().into()))?;
+    if installed(version, locations) {
+        check_installed_target(version, &target, locations)?;
         info!("dfx {version} is already installed");
         return Ok(());
     }
     create_dir_all(locations.versions_dir())?;
 
-    info!("installing dfx {version}");
+    info!("installing dfx {version} from {}", tarball_path.display());
 
-    let download_dir = tempfile::Builder::new()
-        .prefix("dfxvm-download")
-        .tempdir()
-        .map_err(CreateTempDir)?;
-
-    let downloaded_tarball_path =
-        download_verified_tarball(&version, download_dir.path(), &settings).await?;
-
-    let install_dir = tempfile::Builder::new()
-        .prefix(".install")
-        .tempdir_in(locations.versions_dir())
-        .map_err(|source| CreateTempDirIn {
-            path: locations.versions_dir().to_path_buf(),
+    let computed_hash = hash_file(tarball_path)?;
+    match sha256 {
+        Some(expected) => verify_hash(computed_hash, &expected)?,
+        None => {
+            let shasum_path = sibling_path(tarball_path, ".sha256");
+            if !shasum_path.exists() {
+                return Err(NoChecksum {
+                    tarball: tarball_path.to_path_buf(),
+                    shasum: shasum_path,
+                });
+            }
+            verify_checksum(computed_hash, &shasum_path)?;
+        }
+    }
+
+    if let Some(trusted_keys) = settings.trusted_keys()? {
+        let signature_path = sibling_path(tarball_path, ".minisig");
+        if !signature_path.exists() {
+            return Err(NoSignature {
+                tarball: tarball_path.to_path_buf(),
+                signature: signature_path,
+            });
+        }
+        trusted_keys.verify_file(tarball_path, &signature_path)?;
+        info!("verified signature {}", signature_path.display());
+    }
+
+    install_tarball(
+        version,
+        &target,
+        tarball_path,
+        locations,
+        &MultiProgress::new(),
+    )?;
+    if warm_cache || settings.warm_cache() {
+        warm_dfx_cache(version, locations)?;
+    }
+    Ok(())
+}
+
+// Runs `dfx cache install`, so that dfx unpacks its bundled assets into its
+// cache now, rather than the first time it runs.
+fn warm_dfx_cache(version: &Version, locations: &Locations) -> Result<(), WarmCacheError> {
+    let toolchain = Toolchain::Release(version.clone());
+    if let Some(target) = installed_target(&toolchain, locations).filter(|t| !t.runs_here()) {
+        info!("not warming the dfx cache for dfx {version}, which is built for {target}");
+        return Ok(());
+    }
+    let path = locations.dfx_bin_path(&toolchain);
+    info!("warming the dfx cache for dfx {version}");
+    let status = Command::new(&path)
+        .args(["cache", "install"])
+        .env("DFX_VERSION", version.to_string())
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(|source| RunDfx {
+            path: path.clone(),
             source,
         })?;
+    if !status.success() {
+        return Err(CacheInstallFailed {
+            version: version.clone(),
+            status,
+        });
+    }
+    let cache_dir = locations
+        .dfinity_cache_versions_dir()
+        .join(version.to_string());
+    info!("warmed the dfx cache in {}", cache_dir.display());
+    Ok(())
+}
+
+// The path with a suffix appended to its filename, such as a tarball's .sha256 file
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut sibling = path.as_os_str().to_owned();
+    sibling.push(suffix);
+    PathBuf::from(sibling)
+}
+
+// Extracts into a temporary directory in versions_dir, then renames into place,
+// so that a partially extracted version is never visible.
+fn install_tarball(
+    version: &Version,
+    target: &Target,
+    tarball_path: &Path,
+    locations: &Locations,
+    progress: &MultiProgress,
+) -> Result<(), InstallTarballError> {
+    let toolchain = Toolchain::Release(version.clone());
+    let journal = JournalEntry::begin(
+        &Operation::Install {
+            toolchain: toolchain.clone(),
+            staged: None,
+        },
+        locations,
+    )?;
+    let staging_dir = locations.install_staging_dir(&toolchain);
+    if staging_dir.exists() {
+        remove_dir_all(&staging_dir)?;
+    }
+
+    let installed = stage_and_rename(
+        version,
+        target,
+        tarball_path,
+        &staging_dir,
+        &journal,
+        locations,
+        progress,
+    );
 
-    extract_binary(&downloaded_tarball_path, install_dir.path())?;
+    // whether or not the install succeeded, there is nothing left to recover
+    let finished = clean_up_staging_dir(&staging_dir, journal);
+    installed?;
+    finished?;
 
-    let tarball_basename = format_tarball_basename(&version);
-    rename(&install_dir.path().join(tarball_basename), &version_dir)?;
+    progress.suspend(|| info!("installed dfx {version}"));
 
-    info!("installed dfx {version}");
+    Ok(())
+}
 
+fn clean_up_staging_dir(
+    staging_dir: &Path,
+    journal: JournalEntry,
+) -> Result<(), InstallTarballError> {
+    if staging_dir.exists() {
+        remove_dir_all(staging_dir)?;
+    }
+    journal.finish()?;
     Ok(())
 }
 
+fn stage_and_rename(
+    version: &Version,
+    target: &Target,
+    tarball_path: &Path,
+    staging_dir: &Path,
+    journal: &JournalEntry,
+    locations: &Locations,
+    progress: &MultiProgress,
+) -> Result<(), InstallTarballError> {
+    create_dir_all(staging_dir)?;
+    extract_binary(tarball_path, staging_dir, progress)?;
+
+    let extracted_dir = staging_dir.join(format_tarball_basename(target));
+    smoke_test(version, target, &extracted_dir, progress)?;
+    write(extracted_dir.join(TARGET_FILENAME), target.to_string())?;
+
+    // once the staged tree is on disk, recovery can finish the install
+    sync_tree(&extracted_dir)?;
+    journal.record(&Operation::Install {
+        toolchain: Toolchain::Release(version.clone()),
+        staged: Some(extracted_dir.clone()),
+    })?;
+
+    rename(&extracted_dir, &locations.version_dir(version))?;
+    sync(locations.versions_dir())?;
+
+    Ok(())
+}
+
+// What downloading a release's files needs, besides the release and the mirror
+pub(super) struct DownloadContext<'a> {
+    pub client: HttpClient,
+    pub download_dir: &'a Path,
+    pub settings: &'a Settings,
+    pub locations: &'a Locations,
+    pub progress: &'a MultiProgress,
+}
+
+// Downloads the release archive from the first mirror that serves it with the
+// expected checksum.  The expected checksum comes from dfxvm.lock if it has one
+// for the release, and otherwise from the .sha256 file on the mirror.
 async fn download_verified_tarball(
     version: &Version,
+    target: &Target,
     download_dir: &Path,
     settings: &Settings,
+    locations: &Locations,
+    progress: &MultiProgress,
 ) -> Result<PathBuf, DownloadVerifiedTarballError> {
-    let tarball_basename = format_tarball_basename(version);
-    let tarball_filename = format!("{tarball_basename}.tar.gz");
-    let shasum_filename = format!("{tarball_filename}.sha256");
-    let downloaded_tarball_path = download_dir.join(tarball_filename);
-    let downloaded_shasum_path = download_dir.join(shasum_filename);
+    let ctx = DownloadContext {
+        client: http::client(settings)?,
+        download_dir,
+        settings,
+        locations,
+        progress,
+    };
+    let trusted_keys = settings.trusted_keys()?;
 
-    let tarball_url = format_tarball_url(version, tarball_basename, settings)?;
-    let shasum_url = Url::parse(&format!("{tarball_url}.sha256"))?;
+    let locked = find_locked_release(version, target)?;
+    let url_templates = match &locked {
+        Some((lockfile_path, locked)) => {
+            progress.suspend(|| {
+                info!("using checksum from {}", lockfile_path.display());
+            });
+            locked_tarball_urls(version, target, locked, settings)
+        }
+        None => settings.download_url_templates(),
+    };
 
-    let client = Client::new();
+    try_mirrors(&url_templates, progress, |url_template| {
+        download_from_mirror(
+            &ctx,
+            version,
+            target,
+            locked.as_ref(),
+            trusted_keys.as_ref(),
+            url_template,
+        )
+    })
+    .await
+}
 
-    // download the shasum file first because it's smaller
-    download_file(&client, &shasum_url, &downloaded_shasum_path)
-        .await
-        .map_err(|e| match e {
-            DownloadFileError::Status(WrappedReqwestError(status_err))
-                if matches!(status_err.status(),
-                    Some(status) if status == StatusCode::NOT_FOUND) =>
-            {
-                NoSuchVersion(WrappedReqwestError(status_err))
+// Each mirror's url for a locked archive, then the url it was locked from
+fn locked_tarball_urls(
+    version: &Version,
+    target: &Target,
+    locked: &LockedRelease,
+    settings: &Settings,
+) -> Vec<String> {
+    let basename = format_tarball_basename(target);
+    let mut urls = vec![];
+    if let Some(format) = ArchiveFormat::of_path(Path::new(&locked.url)) {
+        for url_template in settings.download_url_templates() {
+            let url = expand_url_template(&url_template, version, &basename, format);
+            if ArchiveFormat::of_path(Path::new(&url)) == Some(format) {
+                urls.push(url);
             }
-            other => DownloadFile(other),
-        })?;
+        }
+    }
+    urls.push(locked.url.clone());
+    urls.into_iter().unique().collect()
+}
+
+// Reuses a cached tarball if its checksum matches the expected one,
+// and otherwise adds the downloaded tarball to the cache.
+async fn download_from_mirror(
+    ctx: &DownloadContext<'_>,
+    version: &Version,
+    target: &Target,
+    locked: Option<&(PathBuf, LockedRelease)>,
+    trusted_keys: Option<&TrustedKeys>,
+    url_template: &str,
+) -> Result<PathBuf, DownloadVerifiedTarballError> {
+    let DownloadContext {
+        client,
+        download_dir,
+        settings,
+        locations,
+        progress,
+    } = ctx;
+
+    let (tarball_url, expected_hash) = match locked {
+        // the urls of locked archives are already expanded
+        Some((_, locked)) => (
+            Url::parse(url_template)?,
+            locked.sha256.to_ascii_lowercase(),
+        ),
+        None => {
+            let (tarball_url, shasum_path) =
+                download_checksum_file(ctx, version, target, url_template).await?;
+            (tarball_url, read_checksum_file(&shasum_path)?)
+        }
+    };
+    let tarball_filename = tarball_url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default();
+    let downloaded_tarball_path = download_dir.join(tarball_filename);
+
+    // a cached tarball is checked against the signature too
+    let signature = match trusted_keys {
+        Some(trusted_keys) => {
+            let path = download_signature(client, &tarball_url, download_dir, progress).await?;
+            Some((trusted_keys.clone(), path))
+        }
+        None => None,
+    };
+    let cached = {
+        let expected_hash = expected_hash.clone();
+        let download_dir = download_dir.to_path_buf();
+        let locations = (*locations).clone();
+        unblock(move || find_cached_download(&expected_hash, &download_dir, &locations)).await
+    };
+    if let Some(cached_tarball_path) = cached {
+        progress.suspend(|| info!("using cached download of {tarball_url}"));
+        verify_tarball_signature(signature.as_ref(), &cached_tarball_path, progress).await?;
+        return Ok(cached_tarball_path);
+    }
 
-    let computed_hash = download_file(&client, &tarball_url, &downloaded_tarball_path).await?;
+    let computed_hash = download_file(
+        client,
+        &tarball_url,
+        &downloaded_tarball_path,
+        settings.download_connections(),
+        progress,
+    )
+    .await?;
 
-    verify_checksum(computed_hash, &downloaded_shasum_path)?;
+    let verified = progress.suspend(|| verify_hash(computed_hash, &expected_hash));
+    match (locked, verified) {
+        (Some((lockfile_path, _)), Err(HashMismatch { expected, actual })) => {
+            return Err(LockfileMismatch {
+                url: tarball_url.to_string(),
+                lockfile: lockfile_path.clone(),
+                expected,
+                actual,
+            });
+        }
+        (_, verified) => verified?,
+    }
+    verify_tarball_signature(signature.as_ref(), &downloaded_tarball_path, progress).await?;
 
+    // the cache is only an optimization, so failing to add to it is not an error
+    if let Err(e) = add_to_cache(
+        &downloaded_tarball_path,
+        &expected_hash,
+        &tarball_url,
+        locations,
+    ) {
+        progress.suspend(|| {
+            info!("failed to add {tarball_url} to the download cache");
+            log_error(&e);
+        });
+    }
     Ok(downloaded_tarball_path)
 }
 
-#[allow(unused_variables)]
-fn format_tarball_basename(version: &Version) -> &'static str {
-    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    return "dfx-x86_64-unknown-linux-gnu";
-    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-    return "dfx-aarch64-unknown-linux-gnu";
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    return "dfx-x86_64-apple-darwin";
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    {
-        // This is the first version that supports aarch64-apple-darwin.
-        let aarch64_apple_darwin_version = Version::parse("0.28.0-beta.1").unwrap();
-        if version >= &aarch64_apple_darwin_version {
-            return "dfx-aarch64-apple-darwin";
+// Checks a tarball against its signature, if there is one to check
+async fn verify_tarball_signature(
+    signature: Option<&(TrustedKeys, PathBuf)>,
+    tarball_path: &Path,
+    progress: &MultiProgress,
+) -> Result<(), VerifyFileSignatureError> {
+    let Some((trusted_keys, signature_path)) = signature.cloned() else {
+        return Ok(());
+    };
+    let name = signature_path.display().to_string();
+    let tarball_path = tarball_path.to_path_buf();
+    unblock(move || trusted_keys.verify_file(&tarball_path, &signature_path)).await?;
+    progress.suspend(|| info!("verified signature {name}"));
+    Ok(())
+}
+
+// Downloads the .sha256 file of the release archive in the first of the
+// archive formats that the mirror has the release in.  Returns the archive's
+// url, and the path to its downloaded checksum file.
+pub(super) async fn download_checksum_file(
+    ctx: &DownloadContext<'_>,
+    version: &Version,
+    target: &Target,
+    url_template: &str,
+) -> Result<(Url, PathBuf), DownloadVerifiedTarballError> {
+    let tarball_basename = format_tarball_basename(target);
+
+    // download the shasum file first because it's smaller, and because
+    // whether it exists says whether the release has an archive in a format
+    let mut formats = archive_formats(version, url_template, ctx.settings, ctx.locations)
+        .into_iter()
+        .peekable();
+    loop {
+        let format = formats.next().expect("at least one archive format");
+        let tarball_filename = format!("{tarball_basename}.{format}");
+        let downloaded_shasum_path = ctx.download_dir.join(format!("{tarball_filename}.sha256"));
+        let tarball_url = Url::parse(&expand_url_template(
+            url_template,
+            version,
+            &tarball_basename,
+            format,
+        ))?;
+        let shasum_url = Url::parse(&format!("{tarball_url}.sha256"))?;
+
+        match download_file(
+            &ctx.client,
+            &shasum_url,
+            &downloaded_shasum_path,
+            1,
+            ctx.progress,
+        )
+        .await
+        {
+            Ok(_) => return Ok((tarball_url, downloaded_shasum_path)),
+            Err(DownloadFileError::Status(status_err))
+                if status_err.status() == Some(StatusCode::NOT_FOUND) =>
+            {
+                if formats.peek().is_none() {
+                    return Err(NoSuchVersion(status_err.error));
+                }
+            }
+            Err(other) => return Err(DownloadFile(other)),
         }
-        "dfx-x86_64-apple-darwin"
     }
 }
 
-fn format_tarball_url(
+fn format_tarball_basename(target: &Target) -> String {
+    format!("dfx-{target}")
+}
+
+// The formats to try downloading a release in, in order: the preferred formats
+// from settings, less any that the manifest says the release doesn't have.
+// A template without {{archive-format}} names a single format.
+fn archive_formats(
     version: &Version,
-    basename: &str,
+    url_template: &str,
     settings: &Settings,
-) -> Result<Url, url::ParseError> {
-    let url_template = settings.download_url_template();
-    let url = url_template
+    locations: &Locations,
+) -> Vec<ArchiveFormat> {
+    if !url_template.contains("{{archive-format}}") {
+        let format = ArchiveFormat::of_path(Path::new(url_template));
+        return vec![format.unwrap_or(ArchiveFormat::Gz)];
+    }
+
+    let preferred = settings.archive_formats();
+    let Some(declared) = declared_archive_formats(version, settings, locations) else {
+        return preferred;
+    };
+    let available: Vec<ArchiveFormat> = preferred
+        .iter()
+        .copied()
+        .filter(|format| declared.contains(format))
+        .collect();
+    // if none of the preferred formats are declared, the manifest may be out of date
+    if available.is_empty() {
+        preferred
+    } else {
+        available
+    }
+}
+
+fn expand_url_template(
+    url_template: &str,
+    version: &Version,
+    basename: &str,
+    format: ArchiveFormat,
+) -> String {
+    url_template
         .replace("{{version}}", &version.to_string())
         .replace("{{basename}}", basename)
-        .replace("{{archive-format}}", "tar.gz");
-    Url::parse(&url)
+        .replace("{{archive-format}}", format.extension())
 }
 
-fn extract_binary(tar_gz_path: &Path, dest: &Path) -> Result<(), ExtractArchiveError> {
-    let spinner = ProgressBar::new_spinner();
+fn extract_binary(
+    archive_path: &Path,
+    dest: &Path,
+    progress: &MultiProgress,
+) -> Result<(), ExtractArchiveError> {
+    let spinner = progress.add(ProgressBar::new_spinner());
     spinner.set_style(
         ProgressStyle::default_spinner()
             .template("{msg} {spinner}")
@@ -141,8 +761,14 @@ fn extract_binary(tar_gz_path: &Path, dest: &Path) -> Result<(), ExtractArchiveE
     spinner.set_message("extracting archive...");
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    let tar_gz = open_file(tar_gz_path)?;
-    let tar = GzDecoder::new(tar_gz);
+    let format = ArchiveFormat::of_path(archive_path).ok_or_else(|| UnknownFormat {
+        path: archive_path.to_path_buf(),
+    })?;
+    let compressed = open_file(archive_path)?;
+    let tar = format.decoder(compressed).map_err(|source| Decompress {
+        path: archive_path.to_path_buf(),
+        source,
+    })?;
     let mut archive = Archive::new(tar);
     archive.unpack(dest).map_err(|source| Unpack {
         path: dest.to_path_buf(),
@@ -150,6 +776,157 @@ fn extract_binary(tar_gz_path: &Path, dest: &Path) -> Result<(), ExtractArchiveE
     })?;
 
     spinner.finish_and_clear();
-    info!("extracted archive");
+    progress.suspend(|| info!("extracted archive"));
     Ok(())
 }
+
+// Checks that the extracted dfx binary is executable, is built for the
+// target, and reports the requested version, before it is moved into place.
+// A binary that can't run on this machine is not run.
+fn smoke_test(
+    version: &Version,
+    target: &Target,
+    extracted_dir: &Path,
+    progress: &MultiProgress,
+) -> Result<(), SmokeTestError> {
+    let path = extracted_dir.join("dfx");
+    let metadata = std::fs::metadata(&path).map_err(|source| NoDfxBinary {
+        path: path.clone(),
+        source,
+    })?;
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Err(NotExecutable { path });
+    }
+
+    let expected = expected_platform(target);
+    if let Some(found) = binary_platform(&path)? {
+        if found != expected {
+            return Err(WrongArchitecture { expected, found });
+        }
+    }
+
+    if !target.runs_here() {
+        progress.suspend(|| info!("not running dfx {version}, which is built for {target}"));
+        return Ok(());
+    }
+
+    let reported = reported_version(&path, extracted_dir)?;
+    if &reported != version {
+        return Err(VersionMismatch {
+            expected: version.clone(),
+            reported,
+        });
+    }
+    progress.suspend(|| info!("checked that dfx {version} runs"));
+    Ok(())
+}
+
+// The platform a target's binaries are built for, such as "x86_64-linux"
+fn expected_platform(target: &Target) -> String {
+    format!("{}-{}", target.arch(), target.os().unwrap_or("unknown"))
+}
+
+// The platform of an ELF or 64-bit Mach-O binary, or None for anything else,
+// such as a script or a universal binary.
+fn binary_platform(path: &Path) -> Result<Option<String>, SmokeTestError> {
+    let read_error = |source| ReadBinary {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut header = vec![];
+    std::fs::File::open(path)
+        .map_err(read_error)?
+        .take(20)
+        .read_to_end(&mut header)
+        .map_err(read_error)?;
+
+    let platform = match header.as_slice() {
+        [0x7f, b'E', b'L', b'F', _, 1, ..] if header.len() >= 20 => {
+            let arch = match u16::from_le_bytes([header[18], header[19]]) {
+                62 => "x86_64".to_string(),
+                183 => "aarch64".to_string(),
+                machine => format!("ELF machine {machine}"),
+            };
+            Some(format!("{arch}-linux"))
+        }
+        [0xcf, 0xfa, 0xed, 0xfe, a, b, c, d, ..] => {
+            let arch = match u32::from_le_bytes([*a, *b, *c, *d]) {
+                0x0100_0007 => "x86_64".to_string(),
+                0x0100_000c => "aarch64".to_string(),
+                cpu_type => format!("Mach-O cpu type {cpu_type:#x}"),
+            };
+            Some(format!("{arch}-macos"))
+        }
+        _ => None,
+    };
+    Ok(platform)
+}
+
+// Runs dfx --version in the staging directory, and parses the version it
+// reports, as in "dfx 0.15.0".  Installs run this off the download thread,
+// so waiting here doesn't hold up other downloads.
+fn reported_version(path: &Path, dir: &Path) -> Result<Version, SmokeTestError> {
+    let run_error = |source| Run {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut child = Command::new(path)
+        .arg("--version")
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(run_error)?;
+
+    // read the output as it comes, so that a full pipe can't stall the child
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(run_error)? {
+            break status;
+        }
+        if start.elapsed() > SMOKE_TEST_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TimedOut {
+                path: path.to_path_buf(),
+                seconds: SMOKE_TEST_TIMEOUT.as_secs(),
+            });
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+
+    if !output.status.success() {
+        return Err(VersionFailed {
+            path: path.to_path_buf(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .split_whitespace()
+        .find_map(|word| Version::parse(word).ok())
+        .ok_or_else(|| VersionNotReported {
+            path: path.to_path_buf(),
+            output: stdout.trim().to_string(),
+        })
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut contents = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut contents);
+        }
+        contents
+    })
+}
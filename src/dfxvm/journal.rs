@@ -0,0 +1,216 @@
+use crate::dfx::Toolchain;
+use crate::error::dfxvm::journal::{RecoverError, WriteJournalError};
+use crate::fs::{create_dir_all, read_dir, remove_dir_all, remove_file, rename, sync};
+use crate::json::{load_json_file, save_json_file};
+use crate::locations::Locations;
+use crate::lock::FileLock;
+use crate::log::log_error;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// An install or uninstall that was started, but not known to have finished.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "operation", rename_all = "kebab-case")]
+pub enum Operation {
+    Install {
+        toolchain: Toolchain,
+
+        // set once the extracted toolchain has been synced to disk,
+        // so that recovery can finish renaming it into place
+        staged: Option<PathBuf>,
+    },
+    Uninstall {
+        toolchain: Toolchain,
+    },
+}
+
+impl Operation {
+    fn toolchain(&self) -> &Toolchain {
+        match self {
+            Operation::Install { toolchain, .. } | Operation::Uninstall { toolchain } => toolchain,
+        }
+    }
+}
+
+// Callers must hold the toolchain lock for as long as the entry exists.
+pub struct JournalEntry {
+    path: PathBuf,
+}
+
+impl JournalEntry {
+    pub fn begin(operation: &Operation, locations: &Locations) -> Result<Self, WriteJournalError> {
+        create_dir_all(&locations.journal_dir())?;
+        let entry = Self {
+            path: journal_path(operation.toolchain(), locations),
+        };
+        entry.record(operation)?;
+        Ok(entry)
+    }
+
+    pub fn record(&self, operation: &Operation) -> Result<(), WriteJournalError> {
+        save_json_file(&self.path, operation)?;
+        sync(&self.path)?;
+        if let Some(parent) = self.path.parent() {
+            sync(parent)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), WriteJournalError> {
+        remove_file(&self.path)?;
+        Ok(())
+    }
+}
+
+fn journal_path(toolchain: &Toolchain, locations: &Locations) -> PathBuf {
+    locations.journal_dir().join(format!("{toolchain}.json"))
+}
+
+// Completes or rolls back operations that a previous run of dfxvm or dfx
+// didn't finish, and removes any staging directories they left behind.
+// Failures are reported but not returned, so that they never stop dfx from running.
+pub fn recover_interrupted_operations(locations: &Locations) {
+    for path in journal_entries(locations) {
+        if let Err(e) = recover_journal_entry(&path, locations) {
+            log_error(&e);
+        }
+    }
+    for (path, staging_dir) in leftover_staging_dirs(locations) {
+        if let Err(e) = remove_leftover_staging_dir(&path, &staging_dir, locations) {
+            log_error(&e);
+        }
+    }
+}
+
+fn journal_entries(locations: &Locations) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(&locations.journal_dir()) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect()
+}
+
+fn recover_journal_entry(path: &Path, locations: &Locations) -> Result<(), RecoverError> {
+    let operation: Operation = load_json_file(path)?;
+    let toolchain = operation.toolchain();
+
+    // an entry whose toolchain is locked belongs to an operation still in progress
+    let Some(_lock) = FileLock::try_acquire(&locations.toolchain_lock_path(toolchain))? else {
+        return Ok(());
+    };
+    // the operation may have finished before we took the lock
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let toolchain_dir = locations.toolchain_dir(toolchain);
+    match &operation {
+        Operation::Install {
+            staged: Some(staged),
+            ..
+        } if staged.exists() || toolchain_dir.exists() => {
+            if !toolchain_dir.exists() {
+                rename(staged, &toolchain_dir)?;
+                sync(locations.versions_dir())?;
+            }
+            info!("completed interrupted install of dfx {toolchain}");
+        }
+        Operation::Install { .. } => {
+            info!("rolled back interrupted install of dfx {toolchain}");
+        }
+        Operation::Uninstall { .. } => {
+            let uninstall_dir = locations.uninstall_staging_dir(toolchain);
+            if toolchain_dir.symlink_metadata().is_ok() {
+                remove_path(&uninstall_dir)?;
+                rename(&toolchain_dir, &uninstall_dir)?;
+            }
+            info!("completed interrupted uninstall of dfx {toolchain}");
+        }
+    }
+
+    remove_path(&locations.install_staging_dir(toolchain))?;
+    remove_path(&locations.uninstall_staging_dir(toolchain))?;
+    remove_file(path)?;
+    Ok(())
+}
+
+// Staging directories that no journal entry accounts for
+fn leftover_staging_dirs(locations: &Locations) -> Vec<(PathBuf, StagingDir)> {
+    let Ok(entries) = read_dir(locations.versions_dir()) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let staging_dir = StagingDir::from_name(&name)?;
+            Some((entry.path(), staging_dir))
+        })
+        .filter(|(_, staging_dir)| match staging_dir {
+            StagingDir::Toolchain(toolchain) => !journal_path(toolchain, locations).exists(),
+            StagingDir::Legacy => true,
+        })
+        .collect()
+}
+
+enum StagingDir {
+    // .install-<toolchain> or .uninstall-<toolchain>
+    Toolchain(Toolchain),
+
+    // .install<random>, a temporary directory from a dfxvm that predates the journal
+    Legacy,
+}
+
+impl StagingDir {
+    fn from_name(name: &str) -> Option<Self> {
+        if let Some(toolchain) = name
+            .strip_prefix(".install-")
+            .or_else(|| name.strip_prefix(".uninstall-"))
+        {
+            return toolchain.parse().ok().map(StagingDir::Toolchain);
+        }
+        let suffix = name.strip_prefix(".install")?;
+        let is_random = !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_alphanumeric());
+        is_random.then_some(StagingDir::Legacy)
+    }
+
+    fn lock_path(&self, locations: &Locations) -> PathBuf {
+        match self {
+            StagingDir::Toolchain(toolchain) => locations.toolchain_lock_path(toolchain),
+            StagingDir::Legacy => locations.versions_lock_path(),
+        }
+    }
+}
+
+fn remove_leftover_staging_dir(
+    path: &Path,
+    staging_dir: &StagingDir,
+    locations: &Locations,
+) -> Result<(), RecoverError> {
+    let Some(_lock) = FileLock::try_acquire(&staging_dir.lock_path(locations))? else {
+        return Ok(());
+    };
+    // an install or uninstall may have begun or finished before we took the lock
+    if let StagingDir::Toolchain(toolchain) = staging_dir {
+        if journal_path(toolchain, locations).exists() {
+            return Ok(());
+        }
+    }
+    if !path.exists() {
+        return Ok(());
+    }
+    remove_path(path)?;
+    info!("removed leftover {}", path.display());
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> Result<(), RecoverError> {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => remove_dir_all(path)?,
+        Ok(_) => remove_file(path)?,
+        Err(_) => {}
+    }
+    Ok(())
+}
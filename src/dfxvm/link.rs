@@ -0,0 +1,80 @@
+use crate::dfx::{is_toolchain_name, Toolchain};
+use crate::error::{
+    dfxvm::link::{
+        LinkError,
+        LinkError::{CreateSymlink, InvalidName, NoDfxBinary},
+    },
+    fs::ReadDirError,
+};
+use crate::fs::{canonicalize, create_dir_all, read_dir, remove_dir_all};
+use crate::locations::Locations;
+use crate::lock::FileLock;
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
+
+// A linked toolchain is a directory in versions_dir, named for the toolchain,
+// that contains a dfx symlink to the linked binary.
+pub fn link(name: &str, path: &Path, locations: &Locations) -> Result<(), LinkError> {
+    if !is_toolchain_name(name) {
+        return Err(InvalidName(name.to_string()));
+    }
+
+    let bin_path = if path.is_dir() {
+        path.join("dfx")
+    } else {
+        path.to_path_buf()
+    };
+    if !bin_path.is_file() {
+        return Err(NoDfxBinary(bin_path));
+    }
+    let bin_path = canonicalize(&bin_path)?;
+
+    let toolchain = Toolchain::Custom(name.to_string());
+    let _lock = FileLock::acquire(&locations.toolchain_lock_path(&toolchain))?;
+    let toolchain_dir = locations.toolchain_dir(&toolchain);
+    if toolchain_dir.exists() {
+        remove_dir_all(&toolchain_dir)?;
+    }
+    create_dir_all(&toolchain_dir)?;
+
+    let link_path = toolchain_dir.join("dfx");
+    std::os::unix::fs::symlink(&bin_path, &link_path).map_err(|source| CreateSymlink {
+        path: link_path,
+        target: bin_path.clone(),
+        source,
+    })?;
+
+    info!("linked {name} to {}", bin_path.display());
+    Ok(())
+}
+
+pub fn linked(name: &str, locations: &Locations) -> bool {
+    is_toolchain_name(name)
+        && locations
+            .toolchain_dir(&Toolchain::Custom(name.to_string()))
+            .is_dir()
+}
+
+// Names and binary paths of linked toolchains, sorted by name
+pub fn linked_toolchains(locations: &Locations) -> Result<Vec<(String, PathBuf)>, ReadDirError> {
+    let versions_dir = locations.versions_dir();
+
+    if !versions_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let toolchains = read_dir(versions_dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !is_toolchain_name(&name) {
+                return None;
+            }
+            let target = std::fs::read_link(entry.path().join("dfx")).ok()?;
+            Some((name, target))
+        })
+        .sorted()
+        .collect();
+    Ok(toolchains)
+}
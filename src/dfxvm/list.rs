@@ -1,20 +1,19 @@
+use crate::dfx::Toolchain;
 use crate::dfxvm::cli::ListOpts;
-use crate::dfxvm::manifest::Manifest;
+use crate::dfxvm::install::{installed_target, installed_versions};
+use crate::dfxvm::link::linked_toolchains;
+use crate::dfxvm::manifest::{fetch_manifest, manifest_urls};
 use crate::error::dfxvm::ListError;
-use crate::json::fetch_json;
 use crate::locations::Locations;
 use crate::settings::Settings;
-use itertools::Itertools;
-use reqwest::Url;
-use semver::Version;
 
 pub async fn list(opts: ListOpts, locations: &Locations) -> Result<(), ListError> {
     let settings = Settings::load_or_default(&locations.settings_path())?;
     if opts.available {
-        let url = Url::parse(&settings.manifest_url())?;
+        let urls = manifest_urls(&settings)?;
 
-        info!("fetching {url}");
-        let manifest = fetch_json::<Manifest>(&url).await?;
+        info!("fetching {}", urls[0]);
+        let manifest = fetch_manifest(&urls, &settings, locations).await?;
 
         let count = std::cmp::min(opts.limit, manifest.versions.len());
         let versions = manifest.versions.iter().rev().take(count);
@@ -23,41 +22,39 @@ pub async fn list(opts: ListOpts, locations: &Locations) -> Result<(), ListError
         }
     } else {
         let default_version = settings.default_version;
-
-        for version in installed_versions(locations)? {
-            let default_indicator = if default_version.as_ref() == Some(&version) {
+        let default_indicator = |toolchain: &Toolchain| {
+            if default_version.as_ref() == Some(toolchain) {
                 " (default)"
             } else {
                 ""
-            };
-            println!("{}{}", version, default_indicator);
-        }
-    }
-    Ok(())
-}
+            }
+        };
 
-fn installed_versions(locations: &Locations) -> Result<Vec<Version>, ListError> {
-    let versions_dir = locations.versions_dir();
+        // versions that can't run on this machine show the target they're built for
+        let target_indicator = |toolchain: &Toolchain| match installed_target(toolchain, locations)
+        {
+            Some(target) if !target.runs_here() => format!(" ({target})"),
+            _ => String::new(),
+        };
 
-    if !versions_dir.exists() {
-        return Ok(vec![]);
+        for version in installed_versions(locations)? {
+            let toolchain = Toolchain::Release(version);
+            println!(
+                "{}{}{}",
+                toolchain,
+                target_indicator(&toolchain),
+                default_indicator(&toolchain)
+            );
+        }
+        for (name, bin_path) in linked_toolchains(locations)? {
+            let toolchain = Toolchain::Custom(name);
+            println!(
+                "{} (linked to {}){}",
+                toolchain,
+                bin_path.display(),
+                default_indicator(&toolchain)
+            );
+        }
     }
-
-    let versions = versions_dir
-        .read_dir()
-        .map_err(|source| ListError::ReadDir {
-            path: versions_dir.to_path_buf(),
-            source,
-        })?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
-        .filter_map(|entry| {
-            entry
-                .file_name()
-                .to_str()
-                .and_then(|filename| Version::parse(filename).ok())
-        })
-        .sorted()
-        .collect();
-    Ok(versions)
+    Ok(())
 }
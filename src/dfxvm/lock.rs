@@ -0,0 +1,148 @@
+use crate::dfx::{find_dfx_json, get_version_from_dfx_json, DfxVersionSpec};
+use crate::dfxvm::install::{download_checksum_file, DownloadContext};
+use crate::dfxvm::manifest::resolve_release;
+use crate::download::read_checksum_file;
+use crate::error::dfxvm::lock::{
+    LoadLockfileError, LockError,
+    LockError::{CreateTempDir, NoDfxJson, NoVersions},
+};
+use crate::http;
+use crate::json::{load_json_file, save_json_file};
+use crate::locations::Locations;
+use crate::mirror::try_mirrors;
+use crate::settings::Settings;
+use crate::target::Target;
+use indicatif::MultiProgress;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub const LOCKFILE_NAME: &str = "dfxvm.lock";
+
+// The release archives that a project expects, kept next to its dfx.json.
+// Installs verify against it, so that everyone who installs a version for
+// the project gets the same archive as whoever locked it.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Lockfile {
+    // dfx version -> target -> archive
+    #[serde(default)]
+    pub versions: BTreeMap<Version, BTreeMap<Target, LockedRelease>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct LockedRelease {
+    pub url: String,
+    pub sha256: String,
+}
+
+// The lockfile next to dfx.json, if there is one
+pub fn find_lockfile() -> Result<Option<PathBuf>, LoadLockfileError> {
+    let path = find_dfx_json()?
+        .and_then(|dfx_json| dfx_json.parent().map(|dir| dir.join(LOCKFILE_NAME)))
+        .filter(|path| path.exists());
+    Ok(path)
+}
+
+// The locked archive for a release, and the lockfile that locks it
+pub fn find_locked_release(
+    version: &Version,
+    target: &Target,
+) -> Result<Option<(PathBuf, LockedRelease)>, LoadLockfileError> {
+    let Some(path) = find_lockfile()? else {
+        return Ok(None);
+    };
+    let lockfile: Lockfile = load_json_file(&path)?;
+    let locked = lockfile
+        .versions
+        .get(version)
+        .and_then(|targets| targets.get(target))
+        .cloned();
+    Ok(locked.map(|locked| (path, locked)))
+}
+
+// Records the published checksums of releases in the lockfile, creating it if
+// necessary.  Without versions, refreshes the versions already in the lockfile
+// and the version in dfx.json.  Without targets, refreshes the targets already
+// locked for each version, and the target that `dfxvm install` would use.
+pub async fn lock(
+    specs: Vec<DfxVersionSpec>,
+    targets: Vec<Target>,
+    locations: &Locations,
+) -> Result<(), LockError> {
+    let dfx_json_path = find_dfx_json()?.ok_or(NoDfxJson)?;
+    let lockfile_path = dfx_json_path.with_file_name(LOCKFILE_NAME);
+    let mut lockfile: Lockfile = if lockfile_path.exists() {
+        load_json_file(&lockfile_path)?
+    } else {
+        Lockfile::default()
+    };
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+
+    let mut versions = vec![];
+    if specs.is_empty() {
+        versions.extend(lockfile.versions.keys().cloned());
+        if let Some(sourced) = get_version_from_dfx_json()? {
+            versions.push(resolve_release(&sourced.spec, locations).await?);
+        }
+    }
+    for spec in &specs {
+        versions.push(resolve_release(spec, locations).await?);
+    }
+    versions.sort();
+    versions.dedup();
+    if versions.is_empty() {
+        return Err(NoVersions);
+    }
+
+    // only checksum files are downloaded, so the system temp directory will do
+    let download_dir = tempfile::Builder::new()
+        .prefix("dfxvm-lock")
+        .tempdir()
+        .map_err(CreateTempDir)?;
+    let progress = MultiProgress::new();
+    let ctx = DownloadContext {
+        client: http::client(&settings)?,
+        download_dir: download_dir.path(),
+        settings: &settings,
+        locations,
+        progress: &progress,
+    };
+    let url_templates = settings.download_url_templates();
+
+    for version in versions {
+        let locked_targets = lockfile.versions.entry(version.clone()).or_default();
+        let mut version_targets = targets.clone();
+        if version_targets.is_empty() {
+            version_targets.extend(locked_targets.keys().cloned());
+            version_targets.push(settings.target(&version));
+        }
+        version_targets.sort();
+        version_targets.dedup();
+
+        for target in version_targets {
+            let (url, shasum_path) = try_mirrors(&url_templates, &progress, |url_template| {
+                download_checksum_file(&ctx, &version, &target, url_template)
+            })
+            .await?;
+            let release = LockedRelease {
+                url: url.to_string(),
+                sha256: read_checksum_file(&shasum_path)?,
+            };
+            match locked_targets.insert(target.clone(), release.clone()) {
+                None => info!("locked dfx {version} for {target}: {}", release.sha256),
+                Some(previous) if previous == release => {
+                    info!("dfx {version} for {target} is already locked")
+                }
+                Some(previous) => info!(
+                    "updated dfx {version} for {target}: {} (was {} from {})",
+                    release.sha256, previous.sha256, previous.url
+                ),
+            }
+        }
+    }
+
+    save_json_file(&lockfile_path, &lockfile)?;
+    info!("wrote {}", lockfile_path.display());
+    Ok(())
+}
@@ -1,13 +1,203 @@
+use crate::archive::ArchiveFormat;
+use crate::dfx::DfxVersionSpec;
+use crate::error::{
+    dfxvm::manifest::{
+        ResolveReleaseError,
+        ResolveReleaseError::NoMatchingRelease,
+        ResolveTagError,
+        ResolveTagError::{FetchManifest, UnknownTag},
+    },
+    json::FetchJsonDocError,
+};
+use crate::fs::create_dir_all;
+use crate::json::{fetch_json, load_json_file, save_json_file};
+use crate::locations::Locations;
+use crate::mirror::try_mirrors;
+use crate::settings::Settings;
+use indicatif::MultiProgress;
+use reqwest::Url;
 use semver::Version;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Deserialize)]
-pub struct Tags {
-    pub latest: Version,
+// How long the proxy trusts cached tags before fetching the manifest again
+const TAGS_CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+// tag name ("latest", "beta", ...) -> dfx version
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Tags(BTreeMap<String, Version>);
+
+impl Tags {
+    pub fn get(&self, tag: &str) -> Option<&Version> {
+        self.0.get(tag)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
 }
 
+// dfx version -> the formats its release archives are published in
+pub type ArchiveFormats = BTreeMap<Version, Vec<ArchiveFormat>>;
+
 #[derive(Deserialize)]
 pub struct Manifest {
     pub tags: Tags,
     pub versions: Vec<Version>,
+    #[serde(default)]
+    pub archive_formats: ArchiveFormats,
+}
+
+// The tags and archive formats from the most recently fetched manifest
+#[derive(Deserialize, Serialize)]
+struct TagsCache {
+    manifest_url: String,
+    fetched_at: u64, // seconds since the unix epoch
+    tags: Tags,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    archive_formats: ArchiveFormats,
+}
+
+impl TagsCache {
+    fn is_fresh(&self) -> bool {
+        now().saturating_sub(self.fetched_at) < TAGS_CACHE_MAX_AGE.as_secs()
+    }
+}
+
+// The manifest mirrors from settings, in the order to try them
+pub fn manifest_urls(settings: &Settings) -> Result<Vec<Url>, url::ParseError> {
+    settings
+        .manifest_urls()
+        .iter()
+        .map(|url| Url::parse(url))
+        .collect()
+}
+
+// Fetches the manifest from the first mirror that serves it, and caches its tags
+// for resolve_tag() and its archive formats for declared_archive_formats()
+pub async fn fetch_manifest(
+    urls: &[Url],
+    settings: &Settings,
+    locations: &Locations,
+) -> Result<Manifest, FetchJsonDocError> {
+    let trusted_keys = settings.trusted_keys()?;
+    let trusted_keys = trusted_keys.as_ref();
+    let (url, manifest) = try_mirrors(urls, &MultiProgress::new(), |url| async move {
+        fetch_json::<Manifest>(url, trusted_keys, settings)
+            .await
+            .map(|manifest| (url, manifest))
+    })
+    .await?;
+    if urls.len() > 1 {
+        info!("fetched the manifest from {url}");
+    }
+    save_tags_cache(url, &manifest, locations);
+    Ok(manifest)
+}
+
+// The formats that the most recently fetched manifest says a version's
+// release archives are published in, if it says.  A release's archives
+// don't change once published, so cached formats of any age are used.
+pub fn declared_archive_formats(
+    version: &Version,
+    settings: &Settings,
+    locations: &Locations,
+) -> Option<Vec<ArchiveFormat>> {
+    let urls = manifest_urls(settings).ok()?;
+    load_tags_cache(&urls, locations)?
+        .archive_formats
+        .remove(version)
+}
+
+// Resolves a version for installing: a requirement picks the newest matching
+// release that is not a prerelease.
+pub async fn resolve_release(
+    spec: &DfxVersionSpec,
+    locations: &Locations,
+) -> Result<Version, ResolveReleaseError> {
+    let req = match spec {
+        DfxVersionSpec::Exact(version) => return Ok(version.clone()),
+        DfxVersionSpec::Tag(tag) => return Ok(resolve_tag(tag, locations).await?),
+        DfxVersionSpec::Requirement(req) => req,
+    };
+
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let urls = manifest_urls(&settings)?;
+    let manifest = fetch_manifest(&urls, &settings, locations).await?;
+    let version = manifest
+        .versions
+        .into_iter()
+        .filter(|v| v.pre.is_empty() && req.matches(v))
+        .max()
+        .ok_or_else(|| NoMatchingRelease(req.clone()))?;
+
+    info!("selected dfx {version}, the newest release matching {req}");
+    Ok(version)
+}
+
+// Looks up the version for a tag, using cached tags if they are recent enough.
+// If the manifest can't be fetched, falls back to cached tags of any age.
+pub async fn resolve_tag(tag: &str, locations: &Locations) -> Result<Version, ResolveTagError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    let urls = manifest_urls(&settings)?;
+
+    let cache = load_tags_cache(&urls, locations);
+    let tags = match cache {
+        Some(cache) if cache.is_fresh() => cache.tags,
+        cache => match fetch_manifest(&urls, &settings, locations).await {
+            Ok(manifest) => manifest.tags,
+            Err(source) => match cache {
+                Some(cache) => {
+                    info!("failed to fetch the manifest, so using cached tags");
+                    cache.tags
+                }
+                None => {
+                    return Err(FetchManifest {
+                        tag: tag.to_string(),
+                        source,
+                    })
+                }
+            },
+        },
+    };
+
+    tags.get(tag).cloned().ok_or_else(|| UnknownTag {
+        tag: tag.to_string(),
+        available: tags.names(),
+    })
+}
+
+// Cached tags from any of the manifest mirrors
+fn load_tags_cache(urls: &[Url], locations: &Locations) -> Option<TagsCache> {
+    let path = locations.tags_cache_path();
+    if !path.exists() {
+        return None;
+    }
+    load_json_file::<TagsCache>(&path)
+        .ok()
+        .filter(|cache| urls.iter().any(|url| cache.manifest_url == url.as_str()))
+}
+
+// The cache is only an optimization, so failing to write it is not an error.
+fn save_tags_cache(url: &Url, manifest: &Manifest, locations: &Locations) {
+    let cache = TagsCache {
+        manifest_url: url.to_string(),
+        fetched_at: now(),
+        tags: manifest.tags.clone(),
+        archive_formats: manifest.archive_formats.clone(),
+    };
+    let path = locations.tags_cache_path();
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent);
+    }
+    let _ = save_json_file(&path, &cache);
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
 }
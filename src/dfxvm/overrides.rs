@@ -0,0 +1,113 @@
+use crate::dfxvm::cli::{OverrideCommand, OverrideOpts};
+use crate::dfxvm::install::{install, installed};
+use crate::dfxvm::manifest::resolve_release;
+use crate::error::{
+    dfxvm::overrides::{OverrideDirError, OverrideError, SetOverrideError, UnsetOverrideError},
+    json::LoadJsonFileError,
+};
+use crate::fs::{canonicalize, create_dir_all};
+use crate::locations::Locations;
+use crate::lock::FileLock;
+use crate::settings::Settings;
+use semver::Version;
+use std::path::PathBuf;
+
+pub async fn overrides(opts: OverrideOpts, locations: &Locations) -> Result<(), OverrideError> {
+    match opts.command {
+        OverrideCommand::List(_opts) => list_overrides(locations)?,
+        OverrideCommand::Set(opts) => {
+            let version = resolve_release(&opts.version, locations).await?;
+            set_override(version, opts.path, locations).await?
+        }
+        OverrideCommand::Unset(opts) => unset_override(opts.path, opts.nonexistent, locations)?,
+    }
+    Ok(())
+}
+
+pub async fn set_override(
+    version: Version,
+    dir: Option<PathBuf>,
+    locations: &Locations,
+) -> Result<(), SetOverrideError> {
+    let dir = override_dir(dir)?;
+
+    if installed(&version, locations) {
+        info!("using existing install for dfx {version}");
+    } else {
+        install(version.clone(), None, locations).await?;
+    }
+
+    let _lock = FileLock::acquire(&locations.settings_lock_path())?;
+    let path = locations.settings_path();
+    let mut settings = Settings::load_or_default(&path)?;
+
+    if settings.overrides.get(&dir) == Some(&version) {
+        info!(
+            "dfx {version} is already the override for {}",
+            dir.display()
+        );
+    } else {
+        settings.overrides.insert(dir.clone(), version.clone());
+
+        create_dir_all(locations.config_dir())?;
+        settings.save(&path)?;
+
+        info!("set override for {} to dfx {version}", dir.display());
+    }
+    Ok(())
+}
+
+pub fn unset_override(
+    dir: Option<PathBuf>,
+    nonexistent: bool,
+    locations: &Locations,
+) -> Result<(), UnsetOverrideError> {
+    let _lock = FileLock::acquire(&locations.settings_lock_path())?;
+    let path = locations.settings_path();
+    let mut settings = Settings::load_or_default(&path)?;
+
+    let dirs = if nonexistent {
+        settings
+            .overrides
+            .keys()
+            .filter(|dir| !dir.exists())
+            .cloned()
+            .collect()
+    } else {
+        vec![override_dir(dir)?]
+    };
+
+    let mut removed = false;
+    for dir in dirs {
+        if settings.overrides.remove(&dir).is_some() {
+            info!("removed override for {}", dir.display());
+            removed = true;
+        } else {
+            info!("no override set for {}", dir.display());
+        }
+    }
+
+    if removed {
+        settings.save(&path)?;
+    } else if nonexistent {
+        info!("no overrides for nonexistent directories");
+    }
+    Ok(())
+}
+
+pub fn list_overrides(locations: &Locations) -> Result<(), LoadJsonFileError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+
+    for (dir, version) in &settings.overrides {
+        println!("{}\t{}", dir.display(), version);
+    }
+    Ok(())
+}
+
+fn override_dir(dir: Option<PathBuf>) -> Result<PathBuf, OverrideDirError> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => crate::env::current_dir()?,
+    };
+    Ok(canonicalize(&dir)?)
+}
@@ -0,0 +1,35 @@
+use crate::dfx::{auto_install, set_dfx_env, VersionOrTag};
+use crate::dfxvm::install::installed_target;
+use crate::error::dfxvm::run::{
+    RunError,
+    RunError::{Exec, ForeignTarget, NotInstalled},
+};
+use crate::locations::Locations;
+use std::ffi::OsString;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+pub async fn run(
+    version: VersionOrTag,
+    command: Vec<OsString>,
+    locations: &Locations,
+) -> Result<(), RunError> {
+    let version = version.resolve(locations).await?;
+    auto_install(&version, locations).await?;
+    if !locations.dfx_bin_path(&version).exists() {
+        return Err(NotInstalled(version));
+    }
+    if let Some(target) = installed_target(&version, locations).filter(|t| !t.runs_here()) {
+        return Err(ForeignTarget { version, target });
+    }
+
+    let (program, args) = command.split_first().expect("clap requires a command");
+    let mut command = Command::new(program);
+    command.args(args);
+    set_dfx_env(&mut command, &version, locations);
+    let err = command.exec();
+    Err(Exec {
+        command: Box::new(command),
+        source: err,
+    })
+}
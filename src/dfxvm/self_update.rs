@@ -1,5 +1,6 @@
+use crate::dfxvm::shim::relink_shims;
 use crate::dist_manifest::lookup_latest_version;
-use crate::download::{download_file, verify_checksum};
+use crate::download::{download_file, download_signature, verify_checksum};
 use crate::error::dfxvm::self_update::CleanupSelfUpdaterError;
 use crate::error::dfxvm::{
     self_update::{
@@ -13,16 +14,23 @@ use crate::error::dfxvm::{
     SelfUpdateError::Exec,
 };
 use crate::fs::{open_file, remove_file};
+use crate::http;
 use crate::installation::install_binaries;
 use crate::locations::Locations;
+use crate::lock::FileLock;
 use crate::settings::Settings;
+use crate::target::Target;
 use flate2::read::GzDecoder;
-use reqwest::{Client, Url};
+use indicatif::MultiProgress;
+use reqwest::Url;
 use std::os::unix::prelude::CommandExt;
 use std::path::Path;
 use tar::Archive;
 
 pub async fn self_update(locations: &Locations) -> Result<(), SelfUpdateError> {
+    // exec releases the lock, since the lock file is close-on-exec,
+    // so the self-replace that follows acquires it again
+    let _lock = FileLock::acquire(&locations.self_update_lock_path())?;
     info!("checking for self-update");
     let settings = Settings::load_or_default(&locations.settings_path())?;
     let latest_version = lookup_latest_version(&settings).await?;
@@ -37,7 +45,7 @@ pub async fn self_update(locations: &Locations) -> Result<(), SelfUpdateError> {
     let tarball_url = format_tarball_url(&settings)?;
     let self_update_path = locations.self_update_path();
 
-    download_latest_binary(&tarball_url, &self_update_path, locations).await?;
+    download_latest_binary(&tarball_url, &self_update_path, &settings, locations).await?;
 
     let mut command = std::process::Command::new(self_update_path);
     command.arg("--self-replace");
@@ -49,7 +57,9 @@ pub async fn self_update(locations: &Locations) -> Result<(), SelfUpdateError> {
 }
 
 pub fn self_replace(locations: &Locations) -> Result<(), SelfReplaceError> {
+    let _lock = FileLock::acquire(&locations.self_update_lock_path())?;
     install_binaries(&locations.bin_dir())?;
+    relink_shims(locations)?;
     Ok(())
 }
 
@@ -65,16 +75,7 @@ pub fn cleanup_self_updater(locations: &Locations) -> Result<(), CleanupSelfUpda
 }
 
 fn format_tarball_url(settings: &Settings) -> Result<Url, FormatTarballUrlError> {
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    let architecture = "aarch64-apple-darwin";
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    let architecture = "x86_64-apple-darwin";
-    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-    let architecture = "aarch64-unknown-linux-gnu";
-    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    let architecture = "x86_64-unknown-linux-gnu";
-
-    let basename = format!("dfxvm-{}", architecture);
+    let basename = format!("dfxvm-{}", Target::host());
     let url = format!(
         "{}/{basename}.tar.gz",
         settings.dfxvm_latest_download_root()
@@ -86,6 +87,7 @@ fn format_tarball_url(settings: &Settings) -> Result<Url, FormatTarballUrlError>
 async fn download_latest_binary(
     tarball_url: &Url,
     binary_path: &Path,
+    settings: &Settings,
     locations: &Locations,
 ) -> Result<(), DownloadLatestBinaryError> {
     let shasum_url = Url::parse(&format!("{tarball_url}.sha256"))?;
@@ -101,11 +103,19 @@ async fn download_latest_binary(
     let downloaded_tarball_path = download_dir.path().join("dfxvm.tar.gz");
     let downloaded_shasum_path = download_dir.path().join("dfxvm.tar.gz.sha256");
 
-    let client = Client::new();
+    let client = http::client(settings)?;
+    let progress = MultiProgress::new();
 
-    download_file(&client, &shasum_url, &downloaded_shasum_path).await?;
-    let computed_hash = download_file(&client, tarball_url, &downloaded_tarball_path).await?;
+    download_file(&client, &shasum_url, &downloaded_shasum_path, 1, &progress).await?;
+    let computed_hash =
+        download_file(&client, tarball_url, &downloaded_tarball_path, 1, &progress).await?;
     verify_checksum(computed_hash, &downloaded_shasum_path)?;
+    if let Some(trusted_keys) = settings.trusted_keys()? {
+        let signature_path =
+            download_signature(&client, tarball_url, download_dir.path(), &progress).await?;
+        trusted_keys.verify_file(&downloaded_tarball_path, &signature_path)?;
+        info!("verified signature {}", signature_path.display());
+    }
 
     extract_binary(binary_path, &downloaded_tarball_path)?;
     Ok(())
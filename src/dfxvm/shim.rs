@@ -0,0 +1,104 @@
+use crate::dfx::{DfxVersionSpec, SHIM_PREFIX};
+use crate::dfxvm::cli::{ShimCommand, ShimOpts};
+use crate::error::{
+    dfxvm::shim::{
+        AddShimError, AddShimError::InvalidName, LinkShimError, LinkShimError::DfxvmNotInstalled,
+        RemoveShimError, ShimError,
+    },
+    json::LoadJsonFileError,
+};
+use crate::fs::{copy, create_dir_all, remove_file};
+use crate::locations::Locations;
+use crate::lock::FileLock;
+use crate::settings::Settings;
+use std::fs::hard_link;
+
+pub fn shim(opts: ShimOpts, locations: &Locations) -> Result<(), ShimError> {
+    match opts.command {
+        ShimCommand::Add(opts) => add_shim(&opts.name, opts.version, locations)?,
+        ShimCommand::List(_opts) => list_shims(locations)?,
+        ShimCommand::Remove(opts) => remove_shim(&opts.name, locations)?,
+    }
+    Ok(())
+}
+
+pub fn add_shim(
+    name: &str,
+    version: DfxVersionSpec,
+    locations: &Locations,
+) -> Result<(), AddShimError> {
+    let valid_name = name.len() > SHIM_PREFIX.len()
+        && name.starts_with(SHIM_PREFIX)
+        && !name.contains(std::path::is_separator);
+    if !valid_name {
+        return Err(InvalidName(name.to_string()));
+    }
+
+    let _lock = FileLock::acquire(&locations.settings_lock_path())?;
+    let path = locations.settings_path();
+    let mut settings = Settings::load_or_default(&path)?;
+
+    link_shim(name, locations)?;
+
+    settings.shims.insert(name.to_string(), version.clone());
+    create_dir_all(locations.config_dir())?;
+    settings.save(&path)?;
+
+    info!("added {name}, which runs dfx {version}");
+    Ok(())
+}
+
+pub fn remove_shim(name: &str, locations: &Locations) -> Result<(), RemoveShimError> {
+    let _lock = FileLock::acquire(&locations.settings_lock_path())?;
+    let path = locations.settings_path();
+    let mut settings = Settings::load_or_default(&path)?;
+
+    if settings.shims.remove(name).is_none() {
+        info!("no shim named {name}");
+        return Ok(());
+    }
+    let shim_path = locations.bin_dir().join(name);
+    if shim_path.exists() {
+        remove_file(&shim_path)?;
+    }
+    settings.save(&path)?;
+
+    info!("removed {name}");
+    Ok(())
+}
+
+pub fn list_shims(locations: &Locations) -> Result<(), LoadJsonFileError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+
+    for (name, version) in &settings.shims {
+        println!("{name}\t{version}");
+    }
+    Ok(())
+}
+
+// Shims are links to the dfxvm binary, so they must be re-created
+// when that binary is replaced.
+pub fn relink_shims(locations: &Locations) -> Result<(), LinkShimError> {
+    let settings = Settings::load_or_default(&locations.settings_path())?;
+    for name in settings.shims.keys() {
+        link_shim(name, locations)?;
+    }
+    Ok(())
+}
+
+// create hardlink from the shim to dfxvm, or copy if hardlinking fails
+fn link_shim(name: &str, locations: &Locations) -> Result<(), LinkShimError> {
+    let dfxvm_path = locations.dfxvm_path();
+    if !dfxvm_path.exists() {
+        return Err(DfxvmNotInstalled(dfxvm_path));
+    }
+
+    let shim_path = locations.bin_dir().join(name);
+    if shim_path.exists() {
+        remove_file(&shim_path)?;
+    }
+    if hard_link(&dfxvm_path, &shim_path).is_err() {
+        copy(&dfxvm_path, &shim_path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::dfx::{
+    determine_dfx_version, find_dfx_json, get_version_from_dfx_json, get_version_from_environment,
+    get_version_from_override, get_version_from_settings, get_version_from_toolchain_file,
+    SourcedVersion, VersionOrTag,
+};
+use crate::error::dfxvm::ShowError;
+use crate::locations::Locations;
+
+pub async fn show(version: Option<VersionOrTag>, locations: &Locations) -> Result<(), ShowError> {
+    println!("dfx version sources, in order of precedence:");
+
+    let commandline = version
+        .as_ref()
+        .map_or_else(|| "not specified".to_string(), |v| v.to_string());
+    print_source("command line", commandline);
+
+    print_source(
+        "DFX_VERSION",
+        describe(get_version_from_environment()?, "not set"),
+    );
+    print_source(
+        "override",
+        describe(get_version_from_override(locations)?, "not set"),
+    );
+
+    let dfx_json = match (find_dfx_json()?, get_version_from_dfx_json()?) {
+        (_, Some(sourced)) => describe(Some(sourced), ""),
+        (Some(path), None) => format!("{} has no dfx field", path.display()),
+        (None, None) => "not found".to_string(),
+    };
+    print_source("dfx.json", dfx_json);
+
+    print_source(
+        "toolchain file",
+        describe(get_version_from_toolchain_file()?, "not found"),
+    );
+    print_source(
+        "default_version",
+        describe(get_version_from_settings(locations)?, "not set"),
+    );
+
+    println!();
+    match determine_dfx_version(version, locations).await? {
+        Some((version, source)) => println!("active version: dfx {version} (from {source})"),
+        None => println!("active version: none"),
+    }
+    Ok(())
+}
+
+fn describe(sourced: Option<SourcedVersion>, absent: &str) -> String {
+    match sourced {
+        Some(SourcedVersion { spec, source }) => match source.path() {
+            Some(path) => format!("{spec} ({})", path.display()),
+            None => spec.to_string(),
+        },
+        None => absent.to_string(),
+    }
+}
+
+fn print_source(label: &str, description: String) {
+    println!("  {:<16} {description}", format!("{label}:"));
+}
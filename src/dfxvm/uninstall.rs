@@ -1,20 +1,29 @@
-use crate::dfxvm::install::installed;
+use crate::dfx::VersionOrTag;
+use crate::dfxvm::journal::{JournalEntry, Operation};
 use crate::error::dfxvm::UninstallError;
 use crate::fs::{remove_dir_all, remove_file, rename};
 use crate::locations::Locations;
-use semver::Version;
+use crate::lock::FileLock;
 
-pub fn uninstall(version: Version, locations: &Locations) -> Result<(), UninstallError> {
-    let version_dir = locations.version_dir(&version);
-    if !installed(&version, locations) {
-        info!("dfx {} is not installed", version);
+// Also removes linked toolchains, leaving the binary they link to in place.
+// A linked toolchain takes precedence over a manifest tag of the same name.
+pub async fn uninstall(version: VersionOrTag, locations: &Locations) -> Result<(), UninstallError> {
+    let toolchain = version.resolve(locations).await?;
+    let _lock = FileLock::acquire(&locations.toolchain_lock_path(&toolchain))?;
+    let toolchain_dir = locations.toolchain_dir(&toolchain);
+    if !toolchain_dir.exists() {
+        info!("dfx {} is not installed", toolchain);
         return Ok(());
     }
 
-    info!("uninstalling dfx {}", version);
-    let uninstall_dir = locations
-        .versions_dir()
-        .join(format!(".uninstall-{version}"));
+    info!("uninstalling dfx {}", toolchain);
+    let journal = JournalEntry::begin(
+        &Operation::Uninstall {
+            toolchain: toolchain.clone(),
+        },
+        locations,
+    )?;
+    let uninstall_dir = locations.uninstall_staging_dir(&toolchain);
     if uninstall_dir.exists() {
         if uninstall_dir.is_dir() {
             remove_dir_all(&uninstall_dir)?;
@@ -22,8 +31,9 @@ pub fn uninstall(version: Version, locations: &Locations) -> Result<(), Uninstal
             remove_file(&uninstall_dir)?;
         }
     }
-    rename(&version_dir, &uninstall_dir)?;
+    rename(&toolchain_dir, &uninstall_dir)?;
     remove_dir_all(&uninstall_dir)?;
-    info!("uninstalled dfx {}", version);
+    journal.finish()?;
+    info!("uninstalled dfx {}", toolchain);
     Ok(())
 }
@@ -1,22 +1,20 @@
 use crate::dfxvm::default::set_default;
-use crate::dfxvm::manifest::Manifest;
-use crate::error::dfxvm::UpdateError;
-use crate::json::fetch_json;
+use crate::dfxvm::manifest::{fetch_manifest, manifest_urls};
+use crate::error::dfxvm::{UpdateError, UpdateError::NoLatestTag};
 use crate::locations::Locations;
 use crate::settings::Settings;
-use reqwest::Url;
 
 pub async fn update(locations: &Locations) -> Result<(), UpdateError> {
     let settings = Settings::load_or_default(&locations.settings_path())?;
-    let url = Url::parse(&settings.manifest_url())?;
+    let urls = manifest_urls(&settings)?;
 
-    info!("fetching {url}");
-    let manifest = fetch_json::<Manifest>(&url).await?;
+    info!("fetching {}", urls[0]);
+    let manifest = fetch_manifest(&urls, &settings, locations).await?;
 
-    let latest_version = manifest.tags.latest;
+    let latest_version = manifest.tags.get("latest").ok_or(NoLatestTag)?.clone();
     info!("latest dfx version is {latest_version}");
 
-    set_default(&latest_version, locations).await?;
+    set_default(&latest_version.into(), locations).await?;
 
     Ok(())
 }
@@ -0,0 +1,20 @@
+use crate::dfx::{determine_dfx_version, VersionOrTag};
+use crate::error::dfxvm::{
+    WhichError,
+    WhichError::{NoVersion, NotInstalled},
+};
+use crate::locations::Locations;
+
+pub async fn which(version: Option<VersionOrTag>, locations: &Locations) -> Result<(), WhichError> {
+    let (version, _source) = determine_dfx_version(version, locations)
+        .await?
+        .ok_or(NoVersion)?;
+
+    let bin_path = locations.dfx_bin_path(&version);
+    if !bin_path.exists() {
+        return Err(NotInstalled(version));
+    }
+
+    println!("{}", bin_path.display());
+    Ok(())
+}
@@ -63,7 +63,9 @@ pub async fn execute(plan: &Plan, locations: &Locations) -> Result<(), ExecutePl
 
     match &plan.options.dfx_version {
         DfxVersion::Latest => dfxvm::update(locations).await?,
-        DfxVersion::Specific(version) => dfxvm::set_default(version, locations).await?,
+        DfxVersion::Specific(version) => {
+            dfxvm::set_default(&version.clone().into(), locations).await?
+        }
     }
 
     if plan.options.modify_path {
@@ -27,7 +27,8 @@ pub async fn lookup_latest_version(
             url: dist_manifest_url,
             source,
         })?;
-    let dist_manifest = fetch_json::<DistManifest>(&url).await?;
+    // not signed: it only names the latest release, and the release tarball is signed
+    let dist_manifest = fetch_json::<DistManifest>(&url, None, settings).await?;
     let dfxvm_release = dist_manifest
         .releases
         .iter()
@@ -1,36 +1,61 @@
 use crate::error::{
     download::{
         DownloadFileError,
-        DownloadFileError::{DownloadContents, GetContentLength},
-        VerifyChecksumError,
+        DownloadFileError::{CannotResume, DownloadContents, GetContentLength, RangeNotSupported},
+        DownloadSignatureError,
+        DownloadSignatureError::NoSignature,
+        HashFileError, VerifyChecksumError,
         VerifyChecksumError::{HashMismatch, MalformedChecksumFile},
     },
-    fs::WriteFileError,
+    fs::{OpenFileError, ReadFileError, WriteFileError},
     reqwest::WrappedReqwestError,
-    Retryable,
 };
-use crate::fs::{create_file, read_to_string};
+use crate::fs::{create_file, metadata, open_file, read_to_string};
+use crate::http::{error_for_status, HttpClient};
 use crate::log::log_error;
-use backon::{ExponentialBuilder, Retryable as _};
+use crate::signature::signature_url;
+use futures_util::future::join_all;
 use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::Client;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use reqwest::{Client, Response, StatusCode};
 use sha2::{Digest, Sha256};
+use std::cell::Cell;
 use std::cmp::min;
-use std::io::Write;
-use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use url::Url;
 
+// Splitting a download into parts is only worthwhile if each part is at least this big.
+const MIN_RANGE_SIZE: u64 = 1024 * 1024;
+
 pub struct FileHash(String);
 
+impl FileHash {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 pub fn verify_checksum(hash: FileHash, shasum_path: &Path) -> Result<(), VerifyChecksumError> {
+    let expected = read_checksum_file(shasum_path)?;
+    verify_hash(hash, &expected)
+}
+
+// The hex-encoded sha256 at the start of a .sha256 file, in lowercase
+pub fn read_checksum_file(shasum_path: &Path) -> Result<String, VerifyChecksumError> {
     let contents = read_to_string(shasum_path)?;
     let contents_to_split = contents.clone();
     let mut parts = contents_to_split.split_whitespace();
-    let expected = parts
-        .next()
-        .ok_or(MalformedChecksumFile { contents })?
-        .to_string();
+    let expected = parts.next().ok_or(MalformedChecksumFile { contents })?;
+    Ok(expected.to_ascii_lowercase())
+}
+
+// Compares against a hex-encoded sha256, ignoring case.
+pub fn verify_hash(hash: FileHash, expected: &str) -> Result<(), VerifyChecksumError> {
+    let expected = expected.to_ascii_lowercase();
     let actual = hash.0;
     if expected != actual {
         return Err(HashMismatch { expected, actual });
@@ -40,21 +65,163 @@ pub fn verify_checksum(hash: FileHash, shasum_path: &Path) -> Result<(), VerifyC
     Ok(())
 }
 
+pub fn hash_file(path: &Path) -> Result<FileHash, HashFileError> {
+    let mut sha256 = Sha256::new();
+    hash_into(path, &mut sha256)?;
+    Ok(FileHash(hex::encode(sha256.finalize())))
+}
+
+fn hash_into(path: &Path, sha256: &mut Sha256) -> Result<(), HashFileError> {
+    let mut file = open_file(path)?;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|source| ReadFileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if n == 0 {
+            break;
+        }
+        sha256.update(&buf[..n]);
+    }
+    Ok(())
+}
+
+// Downloads the minisign signature of the file at url into dir, returning its path
+pub async fn download_signature(
+    client: &HttpClient,
+    url: &Url,
+    dir: &Path,
+    progress: &MultiProgress,
+) -> Result<PathBuf, DownloadSignatureError> {
+    let url = signature_url(url);
+    let filename = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or("signature.minisig");
+    let path = dir.join(filename);
+    download_file(client, &url, &path, 1, progress)
+        .await
+        .map_err(|e| match e {
+            DownloadFileError::Status(status_err)
+                if status_err.status() == Some(StatusCode::NOT_FOUND) =>
+            {
+                NoSignature { url: url.clone() }
+            }
+            other => DownloadSignatureError::DownloadFile(other),
+        })?;
+    Ok(path)
+}
+
+// The progress bar is added to `progress`, so that several downloads can run at once.
+// With more than one connection, a large file is downloaded in that many parts
+// at once, if the server accepts range requests.
 pub async fn download_file(
-    client: &Client,
+    client: &HttpClient,
     url: &Url,
     path: &Path,
+    connections: u32,
+    progress: &MultiProgress,
 ) -> Result<FileHash, DownloadFileError> {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    let pb = progress.add(ProgressBar::new(0));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+            .expect("valid template")
+            .progress_chars("#>-"),
+    );
+    pb.set_message(filename.to_string());
+
+    let result = match ranged_download_size(client, url, connections).await {
+        Some(total_size) => {
+            match download_ranges(client, url, path, total_size, connections, &pb, progress).await {
+                Err(RangeNotSupported { .. }) => {
+                    pb.set_position(0);
+                    download_sequential(client, url, path, &pb, progress).await
+                }
+                other => other,
+            }
+        }
+        None => download_sequential(client, url, path, &pb, progress).await,
+    };
+
+    match result {
+        Ok(hash) => {
+            pb.finish();
+            progress.suspend(|| info!("downloaded {}", url));
+            Ok(hash)
+        }
+        Err(e) => {
+            // leave no progress bar for a missing file, which callers may expect
+            match &e {
+                DownloadFileError::Status(status_err)
+                    if status_err.status() == Some(StatusCode::NOT_FOUND) =>
+                {
+                    pb.finish_and_clear()
+                }
+                _ => pb.abandon(),
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn with_retries<T, F, Fut>(
+    client: &HttpClient,
+    progress: &MultiProgress,
+    f: F,
+) -> Result<T, DownloadFileError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DownloadFileError>>,
+{
     let notify = |err: &DownloadFileError, dur: std::time::Duration| {
-        log_error(err);
-        err!("retry in {dur:?}");
+        progress.suspend(|| {
+            log_error(err);
+            err!("retry in {dur:?}");
+        });
     };
 
-    (|| async { attempt_download_file(client, url, path).await })
-        .retry(&ExponentialBuilder::default())
-        .when(|e| e.is_retryable())
-        .notify(notify)
-        .await
+    client.retry_policy.retry(f, notify).await
+}
+
+// The size of the file, if it is worth downloading in parts and the server
+// accepts range requests.  Any failure here falls back to a single download.
+async fn ranged_download_size(client: &Client, url: &Url, connections: u32) -> Option<u64> {
+    if connections <= 1 {
+        return None;
+    }
+    let res = client.head(url.clone()).send().await.ok()?;
+    let res = res.error_for_status().ok()?;
+    let accepts_ranges = res
+        .headers()
+        .get(ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|unit| unit.trim() == "bytes"));
+    let size: u64 = res
+        .headers()
+        .get(CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    (accepts_ranges && size >= MIN_RANGE_SIZE * u64::from(connections)).then_some(size)
+}
+
+// Retries continue from the bytes that earlier attempts already wrote.
+async fn download_sequential(
+    client: &HttpClient,
+    url: &Url,
+    path: &Path,
+    pb: &ProgressBar,
+    progress: &MultiProgress,
+) -> Result<FileHash, DownloadFileError> {
+    create_file(path)?;
+    with_retries(client, progress, || {
+        attempt_download_file(client, url, path, pb, progress)
+    })
+    .await
 }
 
 // h/t https://gist.github.com/giuliano-oliveira/4d11d6b3bb003dba3a1b53f43d81b30d
@@ -62,35 +229,51 @@ async fn attempt_download_file(
     client: &Client,
     url: &Url,
     path: &Path,
+    pb: &ProgressBar,
+    progress: &MultiProgress,
 ) -> Result<FileHash, DownloadFileError> {
-    info!("downloading {}", url);
+    let offset = metadata(path)?.len();
 
-    let pb = ProgressBar::new(0);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-            .expect("valid template")
-            .progress_chars("#>-"),
-    );
-
-    let res = client
-        .get(url.clone())
+    let mut request = client.get(url.clone());
+    if offset > 0 {
+        progress.suspend(|| info!("resuming download of {} at byte {}", url, offset));
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    } else {
+        progress.suspend(|| info!("downloading {}", url));
+    }
+    let res = request
         .send()
         .await
-        .map_err(|e| DownloadFileError::Get(WrappedReqwestError(e)))?
-        .error_for_status()
-        .map_err(|e| DownloadFileError::Status(WrappedReqwestError(e)))?;
-    let total_size = res.content_length().ok_or(GetContentLength {
-        url: url.to_string(),
-    })?;
+        .map_err(|e| DownloadFileError::Get(WrappedReqwestError(e)))?;
+    if offset > 0 && res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Err(cannot_resume(url, path));
+    }
+    let res = error_for_status(res).map_err(DownloadFileError::Status)?;
+
+    // a server that ignores the range sends the whole file
+    let resuming = res.status() == StatusCode::PARTIAL_CONTENT;
+    if resuming && content_range_start(&res) != Some(offset) {
+        return Err(cannot_resume(url, path));
+    }
+    let start = if resuming { offset } else { 0 };
+    let total_size = start
+        + res.content_length().ok_or(GetContentLength {
+            url: url.to_string(),
+        })?;
     pb.set_length(total_size);
+    pb.set_position(start);
+
+    let mut sha256: Sha256 = Sha256::new();
+    let mut file = if resuming {
+        hash_into(path, &mut sha256)?;
+        open_file_for_write(path, start)?
+    } else {
+        create_file(path)?
+    };
 
     // download chunks
-    let mut file = create_file(path)?;
     let mut stream = res.bytes_stream();
-
-    let mut downloaded: u64 = 0;
-    let mut sha256: Sha256 = Sha256::new();
+    let mut downloaded: u64 = start;
 
     while let Some(item) = stream.next().await {
         let chunk = item.map_err(|source| DownloadContents {
@@ -107,8 +290,130 @@ async fn attempt_download_file(
         pb.set_position(new);
     }
 
-    pb.finish();
-    info!("downloaded {}", url);
     let hash = hex::encode(sha256.finalize());
     Ok(FileHash(hash))
 }
+
+// Truncates the partial download, so the next attempt starts over.
+fn cannot_resume(url: &Url, path: &Path) -> DownloadFileError {
+    match create_file(path) {
+        Ok(_) => CannotResume {
+            url: url.to_string(),
+        },
+        Err(e) => e.into(),
+    }
+}
+
+// "bytes <start>-<end>/<size>"
+fn content_range_start(res: &Response) -> Option<u64> {
+    let value = res.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+async fn download_ranges(
+    client: &HttpClient,
+    url: &Url,
+    path: &Path,
+    total_size: u64,
+    connections: u32,
+    pb: &ProgressBar,
+    progress: &MultiProgress,
+) -> Result<FileHash, DownloadFileError> {
+    progress.suspend(|| info!("downloading {} in {} parts", url, connections));
+
+    let file = create_file(path)?;
+    file.set_len(total_size).map_err(|source| WriteFileError {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    pb.set_length(total_size);
+
+    let part_size = total_size.div_ceil(u64::from(connections));
+    let parts = (0..total_size)
+        .step_by(part_size as usize)
+        .map(|start| (start, min(start + part_size, total_size)));
+    let results = join_all(
+        parts.map(|(start, end)| download_range(client, url, path, start, end, pb, progress)),
+    )
+    .await;
+    results.into_iter().collect::<Result<Vec<()>, _>>()?;
+
+    Ok(hash_file(path)?)
+}
+
+// Writes bytes start..end of the file in place.  Retries continue from the
+// bytes that earlier attempts already wrote.
+async fn download_range(
+    client: &HttpClient,
+    url: &Url,
+    path: &Path,
+    start: u64,
+    end: u64,
+    pb: &ProgressBar,
+    progress: &MultiProgress,
+) -> Result<(), DownloadFileError> {
+    let written = Cell::new(0);
+    with_retries(client, progress, || {
+        attempt_download_range(client, url, path, start + written.get(), end, &written, pb)
+    })
+    .await
+}
+
+async fn attempt_download_range(
+    client: &Client,
+    url: &Url,
+    path: &Path,
+    start: u64,
+    end: u64,
+    written: &Cell<u64>,
+    pb: &ProgressBar,
+) -> Result<(), DownloadFileError> {
+    if start >= end {
+        return Ok(());
+    }
+    let res = client
+        .get(url.clone())
+        .header(RANGE, format!("bytes={}-{}", start, end - 1))
+        .send()
+        .await
+        .map_err(|e| DownloadFileError::Get(WrappedReqwestError(e)))?;
+    let res = error_for_status(res).map_err(DownloadFileError::Status)?;
+    if res.status() != StatusCode::PARTIAL_CONTENT || content_range_start(&res) != Some(start) {
+        return Err(RangeNotSupported {
+            url: url.to_string(),
+        });
+    }
+
+    let mut file = open_file_for_write(path, start)?;
+    let mut stream = res.bytes_stream();
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|source| DownloadContents {
+            url: url.to_string(),
+            source: WrappedReqwestError(source),
+        })?;
+        file.write_all(&chunk).map_err(|source| WriteFileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        written.set(written.get() + chunk.len() as u64);
+        pb.inc(chunk.len() as u64);
+    }
+    Ok(())
+}
+
+fn open_file_for_write(path: &Path, position: u64) -> Result<File, DownloadFileError> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|source| OpenFileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    file.seek(SeekFrom::Start(position))
+        .map_err(|source| WriteFileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    Ok(file)
+}
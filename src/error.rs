@@ -5,9 +5,15 @@ pub mod dfxvm_init;
 pub mod download;
 pub mod env;
 pub mod fs;
+pub mod http;
 pub mod installation;
 pub mod json;
+pub mod lock;
+mod mirror_failure;
 pub mod reqwest;
 mod retryable;
+pub mod signature;
+pub mod target;
 
+pub use mirror_failure::MirrorFailure;
 pub use retryable::Retryable;
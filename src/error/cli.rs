@@ -24,6 +24,8 @@ pub enum DetermineModeError {
     #[error("couldn't determine self executable name")]
     NoExeName,
 
-    #[error("unrecognized executable name '{0}'; expect one of: dfx, dfxvm, dfxvm-init")]
+    #[error(
+        "unrecognized executable name '{0}'; expect one of: dfx, dfx-<version>, dfxvm, dfxvm-init"
+    )]
     UnrecognizedExeName(String),
 }
@@ -1,12 +1,21 @@
 use crate::error::{
-    dfxvm::self_update::CleanupSelfUpdaterError, env::GetCurrentDirError,
-    fs::CanonicalizePathError, json::LoadJsonFileError,
+    dfxvm::{manifest::ResolveTagError, self_update::CleanupSelfUpdaterError, InstallError},
+    dfxvm_init::InteractError,
+    env::GetCurrentDirError,
+    fs::{CanonicalizePathError, ReadDirError, ReadToStringError},
+    json::{FetchJsonDocError, LoadJsonFileError},
 };
+use itertools::Itertools;
+use semver::{Version, VersionReq};
+use std::path::PathBuf;
 use std::process::Command;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error(transparent)]
+    AutoInstall(#[from] AutoInstallError),
+
     #[error(transparent)]
     CleanupSelfUpdater(#[from] CleanupSelfUpdaterError),
 
@@ -20,6 +29,27 @@ pub enum Error {
     },
 }
 
+#[derive(Error, Debug)]
+pub enum AutoInstallError {
+    #[error(transparent)]
+    GetAutoInstallMode(#[from] GetAutoInstallModeError),
+
+    #[error(transparent)]
+    Install(#[from] InstallError),
+
+    #[error(transparent)]
+    Interact(#[from] InteractError),
+}
+
+#[derive(Error, Debug)]
+pub enum GetAutoInstallModeError {
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("invalid DFXVM_AUTO_INSTALL value '{0}': expected never, prompt, or always")]
+    ParseEnvironment(String),
+}
+
 #[derive(Error, Debug)]
 pub enum DetermineDfxVersionError {
     #[error(transparent)]
@@ -31,8 +61,20 @@ pub enum DetermineDfxVersionError {
     #[error(transparent)]
     GetVersionFromEnvironment(#[from] GetVersionFromEnvironmentError),
 
+    #[error(transparent)]
+    GetVersionFromOverride(#[from] GetVersionFromOverrideError),
+
+    #[error(transparent)]
+    GetVersionFromProgramName(#[from] GetVersionFromProgramNameError),
+
+    #[error(transparent)]
+    GetVersionFromToolchainFile(#[from] GetVersionFromToolchainFileError),
+
     #[error(transparent)]
     LoadSettings(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    ResolveVersionSpec(#[from] ResolveVersionSpecError),
 }
 
 #[derive(Error, Debug)]
@@ -63,6 +105,90 @@ pub enum GetVersionFromDfxJsonError {
     LoadDfxJson(#[from] LoadJsonFileError),
 }
 
+#[derive(Error, Debug)]
+pub enum GetVersionFromOverrideError {
+    #[error(transparent)]
+    CanonicalizePath(#[from] CanonicalizePathError),
+
+    #[error(transparent)]
+    GetCurrentDir(#[from] GetCurrentDirError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum GetVersionFromProgramNameError {
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("failed to parse version from program name '{name}'")]
+    ParseVersion { name: String, source: semver::Error },
+}
+
+#[derive(Error, Debug)]
+pub enum GetVersionFromToolchainFileError {
+    #[error(transparent)]
+    CanonicalizePath(#[from] CanonicalizePathError),
+
+    #[error(transparent)]
+    GetCurrentDir(#[from] GetCurrentDirError),
+
+    #[error("failed to parse version '{version}' from {path}")]
+    ParseVersion {
+        version: String,
+        path: PathBuf,
+        source: semver::Error,
+    },
+
+    #[error(transparent)]
+    ReadToString(#[from] ReadToStringError),
+}
+
+#[derive(Error, Debug)]
+pub enum ResolveVersionSpecError {
+    #[error(transparent)]
+    ResolveTag(#[from] ResolveTagError),
+
+    #[error(transparent)]
+    ResolveVersionReq(#[from] ResolveVersionReqError),
+}
+
+#[derive(Error, Debug)]
+pub enum ResolveVersionReqError {
+    #[error("no installed dfx version matches '{requirement}', and failed to fetch the released versions")]
+    FetchManifest {
+        requirement: VersionReq,
+        source: FetchJsonDocError,
+    },
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("no installed dfx version matches '{requirement}' from {from}. {}", matching_versions_hint(.available))]
+    NoMatchingVersionInstalled {
+        requirement: VersionReq,
+        from: String,
+        available: Vec<Version>,
+    },
+
+    #[error("failed to parse manifest url")]
+    ParseManifestUrl(#[from] url::ParseError),
+
+    #[error(transparent)]
+    ReadInstalledVersions(#[from] ReadDirError),
+}
+
+fn matching_versions_hint(available: &[Version]) -> String {
+    match available.first() {
+        Some(newest) => format!(
+            "Matching versions available to install: {}. To install the newest, run: dfxvm install {newest}",
+            available.iter().join(", ")
+        ),
+        None => "No released dfx version matches it.".to_string(),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum FindDfxJsonError {
     #[error(transparent)]
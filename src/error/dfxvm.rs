@@ -1,24 +1,51 @@
+use crate::dfx::Toolchain;
 use crate::error::{
-    dfxvm::self_update::CleanupSelfUpdaterError,
-    fs::{RemoveDirAllError, RemoveFileError, RenameError},
+    dfx::{
+        DetermineDfxVersionError, FindDfxJsonError, GetVersionFromDfxJsonError,
+        GetVersionFromEnvironmentError, GetVersionFromOverrideError,
+        GetVersionFromToolchainFileError,
+    },
+    dfxvm::{
+        journal::WriteJournalError,
+        manifest::{ResolveReleaseError, ResolveTagError},
+        self_update::CleanupSelfUpdaterError,
+    },
+    fs::{ReadDirError, RemoveDirAllError, RemoveFileError, RenameError},
     json::{FetchJsonDocError, LoadJsonFileError},
+    lock::AcquireLockError,
 };
-use std::path::PathBuf;
 use thiserror::Error;
 
+pub mod cache;
 pub mod default;
 pub mod install;
+pub mod journal;
+pub mod link;
+pub mod lock;
+pub mod manifest;
+pub mod overrides;
+pub mod run;
 pub mod self_uninstall;
 pub mod self_update;
+pub mod shim;
 
+pub use cache::CacheError;
 pub use default::DefaultError;
 pub use default::SetDefaultError;
-pub use install::InstallError;
+pub use install::{InstallError, InstallFromFileError, InstallVersionsError};
+pub use link::LinkError;
+pub use lock::LockError;
+pub use overrides::OverrideError;
+pub use run::RunError;
 pub use self_uninstall::SelfUninstallError;
 pub use self_update::SelfUpdateError;
+pub use shim::ShimError;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+
     #[error(transparent)]
     CleanupSelfUpdater(#[from] CleanupSelfUpdaterError),
 
@@ -26,22 +53,49 @@ pub enum Error {
     Default(#[from] DefaultError),
 
     #[error(transparent)]
-    Install(#[from] InstallError),
+    InstallFromFile(#[from] InstallFromFileError),
+
+    #[error(transparent)]
+    InstallVersions(#[from] InstallVersionsError),
+
+    #[error(transparent)]
+    Link(#[from] LinkError),
 
     #[error(transparent)]
     List(#[from] ListError),
 
+    #[error(transparent)]
+    Lock(#[from] LockError),
+
+    #[error(transparent)]
+    Override(#[from] OverrideError),
+
+    #[error(transparent)]
+    ResolveRelease(#[from] ResolveReleaseError),
+
+    #[error(transparent)]
+    Run(#[from] RunError),
+
     #[error(transparent)]
     SelfUninstall(#[from] SelfUninstallError),
 
+    #[error(transparent)]
+    Show(#[from] ShowError),
+
     #[error(transparent)]
     SelfUpdate(#[from] SelfUpdateError),
 
+    #[error(transparent)]
+    Shim(#[from] ShimError),
+
     #[error(transparent)]
     Uninstall(#[from] UninstallError),
 
     #[error(transparent)]
     Update(#[from] UpdateError),
+
+    #[error(transparent)]
+    Which(#[from] WhichError),
 }
 
 #[derive(Error, Debug)]
@@ -55,15 +109,15 @@ pub enum ListError {
     #[error("failed to parse manifest url")]
     ParseManifestUrl(#[from] url::ParseError),
 
-    #[error("failed to read directory {path}")]
-    ReadDir {
-        path: PathBuf,
-        source: std::io::Error,
-    },
+    #[error(transparent)]
+    ReadDir(#[from] ReadDirError),
 }
 
 #[derive(Error, Debug)]
 pub enum UninstallError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
     #[error(transparent)]
     RemoveDirAll(#[from] RemoveDirAllError),
 
@@ -72,6 +126,12 @@ pub enum UninstallError {
 
     #[error(transparent)]
     Rename(#[from] RenameError),
+
+    #[error(transparent)]
+    ResolveTag(#[from] ResolveTagError),
+
+    #[error(transparent)]
+    WriteJournal(#[from] WriteJournalError),
 }
 
 #[derive(Error, Debug)]
@@ -82,9 +142,48 @@ pub enum UpdateError {
     #[error(transparent)]
     LoadSettings(#[from] LoadJsonFileError),
 
+    #[error("the manifest has no 'latest' tag")]
+    NoLatestTag,
+
     #[error("failed to parse manifest url")]
     ParseManifestUrl(#[from] url::ParseError),
 
     #[error(transparent)]
     SetDefault(#[from] SetDefaultError),
 }
+
+#[derive(Error, Debug)]
+pub enum ShowError {
+    #[error(transparent)]
+    DetermineDfxVersion(#[from] DetermineDfxVersionError),
+
+    #[error(transparent)]
+    FindDfxJson(#[from] FindDfxJsonError),
+
+    #[error(transparent)]
+    GetVersionFromDfxJson(#[from] GetVersionFromDfxJsonError),
+
+    #[error(transparent)]
+    GetVersionFromEnvironment(#[from] GetVersionFromEnvironmentError),
+
+    #[error(transparent)]
+    GetVersionFromOverride(#[from] GetVersionFromOverrideError),
+
+    #[error(transparent)]
+    GetVersionFromToolchainFile(#[from] GetVersionFromToolchainFileError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum WhichError {
+    #[error(transparent)]
+    DetermineDfxVersion(#[from] DetermineDfxVersionError),
+
+    #[error("unable to determine which dfx version to use")]
+    NoVersion,
+
+    #[error("dfx {0} is not installed")]
+    NotInstalled(Toolchain),
+}
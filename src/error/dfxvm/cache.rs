@@ -0,0 +1,44 @@
+use crate::error::{
+    fs::{CopyFileError, CreateDirAllError, ReadDirError, ReadMetadataError, RemoveDirAllError},
+    json::{LoadJsonFileError, SaveJsonFileError},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CacheError {
+    #[error(transparent)]
+    Clear(#[from] RemoveDirAllError),
+
+    #[error(transparent)]
+    List(#[from] ReadDirError),
+
+    #[error(transparent)]
+    Prune(#[from] PruneCacheError),
+}
+
+#[derive(Error, Debug)]
+pub enum AddToCacheError {
+    #[error(transparent)]
+    CopyFile(#[from] CopyFileError),
+
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
+
+    #[error(transparent)]
+    ReadMetadata(#[from] ReadMetadataError),
+
+    #[error(transparent)]
+    SaveJsonFile(#[from] SaveJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum PruneCacheError {
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    ReadDir(#[from] ReadDirError),
+
+    #[error(transparent)]
+    RemoveDirAll(#[from] RemoveDirAllError),
+}
@@ -1,7 +1,8 @@
 use crate::error::{
-    dfxvm::install::InstallError,
+    dfxvm::{install::InstallError, manifest::ResolveReleaseError},
     fs::CreateDirAllError,
     json::{LoadJsonFileError, SaveJsonFileError},
+    lock::AcquireLockError,
 };
 use thiserror::Error;
 
@@ -10,12 +11,18 @@ pub enum DefaultError {
     #[error(transparent)]
     Display(#[from] DisplayDefaultError),
 
+    #[error(transparent)]
+    ResolveRelease(#[from] ResolveReleaseError),
+
     #[error(transparent)]
     Set(#[from] SetDefaultError),
 }
 
 #[derive(Error, Debug)]
 pub enum SetDefaultError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
     #[error(transparent)]
     CreateDirAll(#[from] CreateDirAllError),
 
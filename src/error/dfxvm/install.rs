@@ -1,15 +1,27 @@
 use crate::error::{
-    download::DownloadVerifiedTarballError,
-    fs::{CreateDirAllError, OpenFileError, RenameError},
+    dfxvm::journal::WriteJournalError,
+    download::{DownloadVerifiedTarballError, HashFileError, VerifyChecksumError},
+    fs::{
+        CreateDirAllError, OpenFileError, RemoveDirAllError, RenameError, SyncDataError,
+        WriteFileError,
+    },
     json::LoadJsonFileError,
+    lock::AcquireLockError,
+    signature::{NoTrustedKeysError, VerifyFileSignatureError},
 };
+use crate::target::Target;
+use semver::Version;
 use std::path::PathBuf;
+use std::process::ExitStatus;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum InstallError {
-    #[error("failed to create a temporary directory")]
-    CreateTempDir(#[source] std::io::Error),
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
 
     #[error("failed to create a temporary directory in {path}")]
     CreateTempDirIn {
@@ -17,11 +29,68 @@ pub enum InstallError {
         source: std::io::Error,
     },
 
+    #[error(transparent)]
+    DownloadVerifiedTarball(#[from] DownloadVerifiedTarballError),
+
+    #[error(transparent)]
+    InstalledForOtherTarget(#[from] InstalledForOtherTargetError),
+
+    #[error(transparent)]
+    InstallTarball(#[from] InstallTarballError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    WarmCache(#[from] WarmCacheError),
+}
+
+#[derive(Error, Debug)]
+pub enum InstallFromFileError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
     #[error(transparent)]
     CreateVersionsDir(#[from] CreateDirAllError),
 
     #[error(transparent)]
-    DownloadVerifiedTarball(#[from] DownloadVerifiedTarballError),
+    HashFile(#[from] HashFileError),
+
+    #[error(transparent)]
+    InstalledForOtherTarget(#[from] InstalledForOtherTargetError),
+
+    #[error(transparent)]
+    InstallTarball(#[from] InstallTarballError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("no checksum for {tarball}: pass --sha256, or put the checksum in {shasum}")]
+    NoChecksum { tarball: PathBuf, shasum: PathBuf },
+
+    #[error("no signature for {tarball}: put its minisign signature in {signature}")]
+    NoSignature {
+        tarball: PathBuf,
+        signature: PathBuf,
+    },
+
+    #[error(transparent)]
+    NoTrustedKeys(#[from] NoTrustedKeysError),
+
+    #[error(transparent)]
+    VerifyChecksum(#[from] VerifyChecksumError),
+
+    #[error(transparent)]
+    VerifySignature(#[from] VerifyFileSignatureError),
+
+    #[error(transparent)]
+    WarmCache(#[from] WarmCacheError),
+}
+
+#[derive(Error, Debug)]
+pub enum InstallTarballError {
+    #[error(transparent)]
+    CreateStagingDir(#[from] CreateDirAllError),
 
     #[error(transparent)]
     ExtractArchive(#[from] ExtractArchiveError),
@@ -29,18 +98,138 @@ pub enum InstallError {
     #[error(transparent)]
     InstallVersionDirectory(#[from] RenameError),
 
+    #[error(transparent)]
+    RemoveStagingDir(#[from] RemoveDirAllError),
+
+    #[error(transparent)]
+    SmokeTest(#[from] SmokeTestError),
+
+    #[error(transparent)]
+    SyncData(#[from] SyncDataError),
+
+    #[error(transparent)]
+    WriteJournal(#[from] WriteJournalError),
+
+    #[error(transparent)]
+    WriteTarget(#[from] WriteFileError),
+}
+
+#[derive(Error, Debug)]
+#[error("dfx {version} is already installed for {installed}.  To install it for {requested}, first run: dfxvm uninstall {version}")]
+pub struct InstalledForOtherTargetError {
+    pub version: Version,
+    pub installed: Target,
+    pub requested: Target,
+}
+
+#[derive(Error, Debug)]
+pub enum InstallVersionsError {
+    #[error("failed to install dfx {version}")]
+    Install {
+        version: Version,
+        source: Box<InstallError>,
+    },
+
+    // each failure has already been reported
+    #[error("failed to install {failed} of {requested} dfx versions")]
+    InstallSeveral { failed: usize, requested: usize },
+
     #[error(transparent)]
     LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("failed to warm the dfx cache for dfx {version}")]
+    WarmCache {
+        version: Version,
+        source: WarmCacheError,
+    },
+
+    // each failure has already been reported
+    #[error("failed to warm the dfx cache for {failed} of {installed} dfx versions")]
+    WarmCacheSeveral { failed: usize, installed: usize },
+}
+
+#[derive(Error, Debug)]
+pub enum WarmCacheError {
+    #[error("`dfx cache install` failed for dfx {version}: {status}")]
+    CacheInstallFailed {
+        version: Version,
+        status: ExitStatus,
+    },
+
+    #[error("failed to run {path}")]
+    Run {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 }
 
 #[derive(Error, Debug)]
 pub enum ExtractArchiveError {
+    #[error("failed to decompress archive {path}")]
+    Decompress {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
     #[error(transparent)]
     OpenFile(#[from] OpenFileError),
 
+    #[error("unknown archive format of {path}: expected a .tar.zst, .tar.xz or .tar.gz file")]
+    UnknownFormat { path: PathBuf },
+
     #[error("failed to unpack archive {path}")]
     Unpack {
         path: PathBuf,
         source: std::io::Error,
     },
 }
+
+// The archive's dfx binary is checked before it is moved into place,
+// so that a broken or mislabelled release is never installed.
+#[derive(Error, Debug)]
+pub enum SmokeTestError {
+    #[error("the archive has no dfx binary at {path}")]
+    NoDfxBinary {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("{path} is not executable")]
+    NotExecutable { path: PathBuf },
+
+    #[error("failed to read {path}")]
+    ReadBinary {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to run {path} --version")]
+    Run {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("{path} --version did not finish within {seconds} seconds")]
+    TimedOut { path: PathBuf, seconds: u64 },
+
+    #[error("{path} --version failed with {status}: {stderr}")]
+    VersionFailed {
+        path: PathBuf,
+        status: ExitStatus,
+        stderr: String,
+    },
+
+    #[error(
+        "the archive's dfx binary reports version {reported}, but dfx {expected} was requested"
+    )]
+    VersionMismatch {
+        expected: Version,
+        reported: Version,
+    },
+
+    #[error("{path} --version did not report a version: {output}")]
+    VersionNotReported { path: PathBuf, output: String },
+
+    #[error("the archive's dfx binary is built for {found}, but {expected} was expected")]
+    WrongArchitecture { expected: String, found: String },
+}
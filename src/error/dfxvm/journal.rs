@@ -0,0 +1,42 @@
+use crate::error::{
+    fs::{CreateDirAllError, RemoveDirAllError, RemoveFileError, RenameError, SyncDataError},
+    json::{LoadJsonFileError, SaveJsonFileError},
+    lock::AcquireLockError,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RecoverError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    LoadJournalEntry(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    RemoveDirAll(#[from] RemoveDirAllError),
+
+    #[error(transparent)]
+    RemoveFile(#[from] RemoveFileError),
+
+    #[error(transparent)]
+    Rename(#[from] RenameError),
+
+    #[error(transparent)]
+    SyncData(#[from] SyncDataError),
+}
+
+#[derive(Error, Debug)]
+pub enum WriteJournalError {
+    #[error(transparent)]
+    CreateJournalDir(#[from] CreateDirAllError),
+
+    #[error(transparent)]
+    RemoveFile(#[from] RemoveFileError),
+
+    #[error(transparent)]
+    Save(#[from] SaveJsonFileError),
+
+    #[error(transparent)]
+    SyncData(#[from] SyncDataError),
+}
@@ -0,0 +1,34 @@
+use crate::error::{
+    fs::{CanonicalizePathError, CreateDirAllError, RemoveDirAllError},
+    lock::AcquireLockError,
+};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LinkError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    Canonicalize(#[from] CanonicalizePathError),
+
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
+
+    #[error("failed to create symlink {path} to {target}")]
+    CreateSymlink {
+        path: PathBuf,
+        target: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid toolchain name '{0}': must start with a letter, and contain only letters, digits, '-' and '_'")]
+    InvalidName(String),
+
+    #[error("no dfx binary at {0}")]
+    NoDfxBinary(PathBuf),
+
+    #[error(transparent)]
+    RemoveDirAll(#[from] RemoveDirAllError),
+}
@@ -0,0 +1,55 @@
+use crate::error::{
+    dfx::{FindDfxJsonError, GetVersionFromDfxJsonError},
+    dfxvm::manifest::ResolveReleaseError,
+    download::{DownloadVerifiedTarballError, VerifyChecksumError},
+    http::BuildHttpClientError,
+    json::{LoadJsonFileError, SaveJsonFileError},
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LoadLockfileError {
+    #[error(transparent)]
+    FindDfxJson(#[from] FindDfxJsonError),
+
+    #[error(transparent)]
+    LoadLockfile(#[from] LoadJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error(transparent)]
+    BuildHttpClient(#[from] BuildHttpClientError),
+
+    #[error("failed to create a temporary directory")]
+    CreateTempDir(#[source] std::io::Error),
+
+    #[error(transparent)]
+    DownloadChecksum(#[from] DownloadVerifiedTarballError),
+
+    #[error(transparent)]
+    FindDfxJson(#[from] FindDfxJsonError),
+
+    #[error(transparent)]
+    GetVersionFromDfxJson(#[from] GetVersionFromDfxJsonError),
+
+    #[error(transparent)]
+    LoadJsonFile(#[from] LoadJsonFileError),
+
+    #[error(
+        "no dfx.json found in this directory or its parents: dfxvm.lock goes next to dfx.json"
+    )]
+    NoDfxJson,
+
+    #[error("no versions to lock: pass a version, or set dfx in dfx.json")]
+    NoVersions,
+
+    #[error(transparent)]
+    ReadChecksum(#[from] VerifyChecksumError),
+
+    #[error(transparent)]
+    ResolveRelease(#[from] ResolveReleaseError),
+
+    #[error(transparent)]
+    SaveLockfile(#[from] SaveJsonFileError),
+}
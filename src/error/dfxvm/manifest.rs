@@ -0,0 +1,39 @@
+use crate::error::json::{FetchJsonDocError, LoadJsonFileError};
+use semver::VersionReq;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ResolveReleaseError {
+    #[error("failed to fetch the released dfx versions")]
+    FetchManifest(#[from] FetchJsonDocError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("no released dfx version matches '{0}'")]
+    NoMatchingRelease(VersionReq),
+
+    #[error("failed to parse manifest url")]
+    ParseManifestUrl(#[from] url::ParseError),
+
+    #[error(transparent)]
+    ResolveTag(#[from] ResolveTagError),
+}
+
+#[derive(Error, Debug)]
+pub enum ResolveTagError {
+    #[error("failed to fetch the dfx manifest to look up tag '{tag}'")]
+    FetchManifest {
+        tag: String,
+        source: FetchJsonDocError,
+    },
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error("failed to parse manifest url")]
+    ParseManifestUrl(#[from] url::ParseError),
+
+    #[error("unknown dfx version tag '{tag}'. Available tags: {}", available.join(", "))]
+    UnknownTag { tag: String, available: Vec<String> },
+}
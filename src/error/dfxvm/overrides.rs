@@ -0,0 +1,68 @@
+use crate::error::{
+    dfxvm::{install::InstallError, manifest::ResolveReleaseError},
+    env::GetCurrentDirError,
+    fs::{CanonicalizePathError, CreateDirAllError},
+    json::{LoadJsonFileError, SaveJsonFileError},
+    lock::AcquireLockError,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OverrideError {
+    #[error(transparent)]
+    List(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    ResolveRelease(#[from] ResolveReleaseError),
+
+    #[error(transparent)]
+    Set(#[from] SetOverrideError),
+
+    #[error(transparent)]
+    Unset(#[from] UnsetOverrideError),
+}
+
+#[derive(Error, Debug)]
+pub enum SetOverrideError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
+
+    #[error(transparent)]
+    Install(#[from] InstallError),
+
+    #[error(transparent)]
+    LoadJsonFile(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    OverrideDir(#[from] OverrideDirError),
+
+    #[error(transparent)]
+    SaveJsonFile(#[from] SaveJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum UnsetOverrideError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    LoadJsonFile(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    OverrideDir(#[from] OverrideDirError),
+
+    #[error(transparent)]
+    SaveJsonFile(#[from] SaveJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum OverrideDirError {
+    #[error(transparent)]
+    CanonicalizePath(#[from] CanonicalizePathError),
+
+    #[error(transparent)]
+    GetCurrentDir(#[from] GetCurrentDirError),
+}
@@ -0,0 +1,26 @@
+use crate::dfx::Toolchain;
+use crate::error::{dfx::AutoInstallError, dfxvm::manifest::ResolveTagError};
+use crate::target::Target;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RunError {
+    #[error(transparent)]
+    AutoInstall(#[from] AutoInstallError),
+
+    #[error("failed to execute {command:#?}")]
+    Exec {
+        command: Box<Command>,
+        source: std::io::Error,
+    },
+
+    #[error("dfx {version} is installed for {target}, so it can't run on this machine")]
+    ForeignTarget { version: Toolchain, target: Target },
+
+    #[error("dfx {0} is not installed.  To install it, run: dfxvm install {0}")]
+    NotInstalled(Toolchain),
+
+    #[error(transparent)]
+    ResolveTag(#[from] ResolveTagError),
+}
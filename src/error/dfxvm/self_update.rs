@@ -1,9 +1,13 @@
 use crate::error::{
-    download::{DownloadFileError, VerifyChecksumError},
+    dfxvm::shim::LinkShimError,
+    download::{DownloadFileError, DownloadSignatureError, VerifyChecksumError},
     env::NoHomeDirectoryError,
     fs::{OpenFileError, RemoveFileError},
+    http::BuildHttpClientError,
     installation::InstallBinariesError,
     json::{FetchJsonDocError, LoadJsonFileError},
+    lock::AcquireLockError,
+    signature::{NoTrustedKeysError, VerifyFileSignatureError},
 };
 use std::path::PathBuf;
 use std::process::Command;
@@ -12,6 +16,9 @@ use url::Url;
 
 #[derive(Error, Debug)]
 pub enum SelfUpdateError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
     #[error(transparent)]
     DownloadLatestBinaryError(#[from] DownloadLatestBinaryError),
 
@@ -55,6 +62,9 @@ pub enum LookupLatestVersionError {
 
 #[derive(Error, Debug)]
 pub enum DownloadLatestBinaryError {
+    #[error(transparent)]
+    BuildHttpClient(#[from] BuildHttpClientError),
+
     #[error("failed to create a temporary directory in {path}")]
     CreateTempDirIn {
         path: PathBuf,
@@ -64,14 +74,23 @@ pub enum DownloadLatestBinaryError {
     #[error(transparent)]
     DownloadFile(#[from] DownloadFileError),
 
+    #[error(transparent)]
+    DownloadSignature(#[from] DownloadSignatureError),
+
     #[error(transparent)]
     ExtractBinary(#[from] ExtractBinaryError),
 
+    #[error(transparent)]
+    NoTrustedKeys(#[from] NoTrustedKeysError),
+
     #[error(transparent)]
     ParseUrl(#[from] url::ParseError),
 
     #[error(transparent)]
     VerifyChecksum(#[from] VerifyChecksumError),
+
+    #[error(transparent)]
+    VerifySignature(#[from] VerifyFileSignatureError),
 }
 
 #[derive(Error, Debug)]
@@ -91,9 +110,15 @@ pub enum ExtractBinaryError {
 
 #[derive(Error, Debug)]
 pub enum SelfReplaceError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
     #[error(transparent)]
     InstallBinaries(#[from] InstallBinariesError),
 
+    #[error(transparent)]
+    LinkShim(#[from] LinkShimError),
+
     #[error(transparent)]
     NoHomeDirectory(#[from] NoHomeDirectoryError),
 }
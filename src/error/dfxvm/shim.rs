@@ -0,0 +1,70 @@
+use crate::error::{
+    fs::{CopyFileError, CreateDirAllError, RemoveFileError},
+    json::{LoadJsonFileError, SaveJsonFileError},
+    lock::AcquireLockError,
+};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShimError {
+    #[error(transparent)]
+    Add(#[from] AddShimError),
+
+    #[error(transparent)]
+    List(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    Remove(#[from] RemoveShimError),
+}
+
+#[derive(Error, Debug)]
+pub enum AddShimError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
+
+    #[error("invalid shim name '{0}': must start with 'dfx-'")]
+    InvalidName(String),
+
+    #[error(transparent)]
+    LinkShim(#[from] LinkShimError),
+
+    #[error(transparent)]
+    LoadJsonFile(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    SaveJsonFile(#[from] SaveJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum RemoveShimError {
+    #[error(transparent)]
+    AcquireLock(#[from] AcquireLockError),
+
+    #[error(transparent)]
+    LoadSettings(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    RemoveFile(#[from] RemoveFileError),
+
+    #[error(transparent)]
+    SaveSettings(#[from] SaveJsonFileError),
+}
+
+#[derive(Error, Debug)]
+pub enum LinkShimError {
+    #[error(transparent)]
+    CopyFile(#[from] CopyFileError),
+
+    #[error("dfxvm is not installed at {0}")]
+    DfxvmNotInstalled(PathBuf),
+
+    #[error(transparent)]
+    LoadJsonFile(#[from] LoadJsonFileError),
+
+    #[error(transparent)]
+    RemoveFile(#[from] RemoveFileError),
+}
@@ -1,27 +1,97 @@
 use crate::error::{
-    fs::{CreateFileError, ReadToStringError, WriteFileError},
-    reqwest::WrappedReqwestError,
-    Retryable,
+    dfxvm::lock::LoadLockfileError,
+    fs::{
+        CreateFileError, OpenFileError, ReadFileError, ReadMetadataError, ReadToStringError,
+        WriteFileError,
+    },
+    http::BuildHttpClientError,
+    reqwest::{StatusError, WrappedReqwestError},
+    signature::{NoTrustedKeysError, VerifyFileSignatureError},
+    MirrorFailure, Retryable,
 };
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
+use url::Url;
 
 #[derive(Error, Debug)]
 pub enum DownloadVerifiedTarballError {
+    #[error(transparent)]
+    BuildHttpClient(#[from] BuildHttpClientError),
+
     #[error(transparent)]
     DownloadFile(#[from] DownloadFileError),
 
+    #[error(transparent)]
+    DownloadSignature(#[from] DownloadSignatureError),
+
+    #[error(transparent)]
+    LoadLockfile(#[from] LoadLockfileError),
+
+    #[error("{url} has sha256 {actual}, but {lockfile} expects {expected}.  If the release was replaced on purpose, run `dfxvm lock` to record its new checksum")]
+    LockfileMismatch {
+        url: String,
+        lockfile: PathBuf,
+        expected: String,
+        actual: String,
+    },
+
     #[error("no such version")]
     NoSuchVersion(#[source] WrappedReqwestError),
 
+    #[error(transparent)]
+    NoTrustedKeys(#[from] NoTrustedKeysError),
+
     #[error("failed to parse url")]
     ParseUrl(#[from] url::ParseError),
 
     #[error(transparent)]
     VerifyChecksum(#[from] VerifyChecksumError),
+
+    #[error(transparent)]
+    VerifySignature(#[from] VerifyFileSignatureError),
+}
+
+// A failed signature check is not a mirror failure: the mirror served the
+// archive its checksum file names, but not one signed by a trusted key.
+impl MirrorFailure for DownloadVerifiedTarballError {
+    fn is_mirror_failure(&self) -> bool {
+        match self {
+            DownloadVerifiedTarballError::DownloadFile(e) => e.is_mirror_failure(),
+            DownloadVerifiedTarballError::DownloadSignature(e) => e.is_mirror_failure(),
+            DownloadVerifiedTarballError::LockfileMismatch { .. } => true,
+            DownloadVerifiedTarballError::NoSuchVersion(_) => true,
+            DownloadVerifiedTarballError::VerifyChecksum(VerifyChecksumError::HashMismatch {
+                ..
+            }) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DownloadSignatureError {
+    #[error(transparent)]
+    DownloadFile(#[from] DownloadFileError),
+
+    #[error("no signature at {url}")]
+    NoSignature { url: Url },
+}
+
+impl MirrorFailure for DownloadSignatureError {
+    fn is_mirror_failure(&self) -> bool {
+        match self {
+            DownloadSignatureError::DownloadFile(e) => e.is_mirror_failure(),
+            DownloadSignatureError::NoSignature { .. } => true,
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum DownloadFileError {
+    #[error("failed to resume download of {url}")]
+    CannotResume { url: String },
+
     #[error(transparent)]
     CreateFile(#[from] CreateFileError),
 
@@ -38,7 +108,19 @@ pub enum DownloadFileError {
     GetContentLength { url: String },
 
     #[error(transparent)]
-    Status(WrappedReqwestError),
+    HashFile(#[from] HashFileError),
+
+    #[error(transparent)]
+    OpenFile(#[from] OpenFileError),
+
+    #[error("{url} does not support range requests")]
+    RangeNotSupported { url: String },
+
+    #[error(transparent)]
+    ReadMetadata(#[from] ReadMetadataError),
+
+    #[error(transparent)]
+    Status(StatusError),
 
     #[error(transparent)]
     WriteFile(#[from] WriteFileError),
@@ -47,11 +129,43 @@ pub enum DownloadFileError {
 impl Retryable for DownloadFileError {
     fn is_retryable(&self) -> bool {
         match self {
+            DownloadFileError::CannotResume { .. } => true,
             DownloadFileError::DownloadContents { .. } => true,
             DownloadFileError::Get(e) => e.is_retryable(),
+            DownloadFileError::Status(e) => e.is_retryable(),
             _ => false,
         }
     }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            DownloadFileError::Status(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+impl MirrorFailure for DownloadFileError {
+    fn is_mirror_failure(&self) -> bool {
+        match self {
+            DownloadFileError::CannotResume { .. }
+            | DownloadFileError::DownloadContents { .. }
+            | DownloadFileError::GetContentLength { .. }
+            | DownloadFileError::RangeNotSupported { .. } => true,
+            DownloadFileError::Get(e) => e.is_mirror_failure(),
+            DownloadFileError::Status(e) => e.is_mirror_failure(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HashFileError {
+    #[error(transparent)]
+    OpenFile(#[from] OpenFileError),
+
+    #[error(transparent)]
+    ReadFile(#[from] ReadFileError),
 }
 
 #[derive(Error, Debug)]
@@ -56,6 +56,13 @@ pub struct ReadFileError {
     pub source: std::io::Error,
 }
 
+#[derive(Error, Debug)]
+#[error("failed to read directory {path}")]
+pub struct ReadDirError {
+    pub path: PathBuf,
+    pub source: std::io::Error,
+}
+
 #[derive(Error, Debug)]
 #[error("failed to read metadata for {path}")]
 pub struct ReadMetadataError {
@@ -0,0 +1,33 @@
+use crate::error::{fs::ReadFileError, reqwest::WrappedReqwestError};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BuildHttpClientError {
+    #[error("failed to build the http client")]
+    Build(#[source] WrappedReqwestError),
+
+    #[error("invalid proxy url '{url}'")]
+    InvalidProxy {
+        url: String,
+        source: WrappedReqwestError,
+    },
+
+    #[error("no certificates found in {path}")]
+    NoCaCertificates { path: PathBuf },
+
+    #[error("failed to parse the certificates in {path}")]
+    ParseCaCertificates {
+        path: PathBuf,
+        source: WrappedReqwestError,
+    },
+
+    #[error("failed to parse the client certificate and key in {path}")]
+    ParseClientCertificate {
+        path: PathBuf,
+        source: WrappedReqwestError,
+    },
+
+    #[error(transparent)]
+    Read(#[from] ReadFileError),
+}
@@ -1,10 +1,14 @@
 use crate::error::{
-    fs::{ReadFileError, WriteFileError},
-    reqwest::WrappedReqwestError,
-    Retryable,
+    fs::{ReadFileError, RenameError, WriteFileError},
+    http::BuildHttpClientError,
+    reqwest::{StatusError, WrappedReqwestError},
+    signature::{NoTrustedKeysError, VerifySignatureError},
+    MirrorFailure, Retryable,
 };
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
+use url::Url;
 
 #[derive(Error, Debug)]
 pub enum LoadJsonFileError {
@@ -26,23 +30,38 @@ pub enum SaveJsonFileError {
         source: serde_json::Error,
     },
 
+    #[error(transparent)]
+    Rename(#[from] RenameError),
+
     #[error(transparent)]
     Write(#[from] WriteFileError),
 }
 
 #[derive(Error, Debug)]
 pub enum FetchJsonDocError {
+    #[error(transparent)]
+    BuildHttpClient(#[from] BuildHttpClientError),
+
     #[error(transparent)]
     Get(WrappedReqwestError),
 
     #[error(transparent)]
-    Status(WrappedReqwestError),
+    Status(StatusError),
 
     #[error(transparent)]
     ReadBytes(WrappedReqwestError),
 
+    #[error("no signature at {url}")]
+    NoSignature { url: Url },
+
+    #[error(transparent)]
+    NoTrustedKeys(#[from] NoTrustedKeysError),
+
     #[error("failed to parse json document")]
     Parse(serde_json::Error),
+
+    #[error(transparent)]
+    VerifySignature(#[from] VerifySignatureError),
 }
 
 impl Retryable for FetchJsonDocError {
@@ -50,6 +69,25 @@ impl Retryable for FetchJsonDocError {
         match self {
             FetchJsonDocError::Get(e) => e.is_retryable(),
             FetchJsonDocError::ReadBytes(_) => true,
+            FetchJsonDocError::Status(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            FetchJsonDocError::Status(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+}
+
+impl MirrorFailure for FetchJsonDocError {
+    fn is_mirror_failure(&self) -> bool {
+        match self {
+            FetchJsonDocError::Get(e) => e.is_mirror_failure(),
+            FetchJsonDocError::Status(e) => e.is_mirror_failure(),
+            FetchJsonDocError::NoSignature { .. } | FetchJsonDocError::ReadBytes(_) => true,
             _ => false,
         }
     }
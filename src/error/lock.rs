@@ -0,0 +1,18 @@
+use crate::error::fs::{CreateDirAllError, OpenFileError};
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AcquireLockError {
+    #[error(transparent)]
+    CreateDirAll(#[from] CreateDirAllError),
+
+    #[error("failed to lock {path}")]
+    Lock {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    OpenFile(#[from] OpenFileError),
+}
@@ -0,0 +1,4 @@
+// Whether another mirror might succeed where this one failed
+pub trait MirrorFailure {
+    fn is_mirror_failure(&self) -> bool;
+}
@@ -1,4 +1,6 @@
-use crate::error::Retryable;
+use crate::error::{MirrorFailure, Retryable};
+use reqwest::StatusCode;
+use std::time::Duration;
 use thiserror::Error;
 
 // reqwest::Error's fmt::Display appends the error descriptions of all sources.
@@ -14,3 +16,55 @@ impl Retryable for WrappedReqwestError {
         err.is_timeout()
     }
 }
+
+// A mirror that can't be reached
+impl MirrorFailure for WrappedReqwestError {
+    fn is_mirror_failure(&self) -> bool {
+        let err = &self.0;
+        err.is_connect() || err.is_timeout() || err.is_request()
+    }
+}
+
+// An error status, and how long the response asked to wait before retrying
+#[derive(Error, Debug)]
+#[error("{error}")]
+pub struct StatusError {
+    pub error: WrappedReqwestError,
+    pub retry_after: Option<Duration>,
+}
+
+impl StatusError {
+    pub fn status(&self) -> Option<StatusCode> {
+        self.error.0.status()
+    }
+}
+
+// Rate limits, and gateways and servers that may recover
+impl Retryable for StatusError {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self.status(),
+            Some(
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+        )
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+}
+
+// A mirror that has an error, is rate limited, or doesn't have the file
+impl MirrorFailure for StatusError {
+    fn is_mirror_failure(&self) -> bool {
+        self.status().is_some_and(|status| {
+            status.is_server_error()
+                || status == StatusCode::NOT_FOUND
+                || status == StatusCode::TOO_MANY_REQUESTS
+        })
+    }
+}
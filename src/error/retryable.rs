@@ -1,3 +1,10 @@
+use std::time::Duration;
+
 pub trait Retryable {
     fn is_retryable(&self) -> bool;
+
+    // How long the server asked to wait before retrying, if it did
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
 }
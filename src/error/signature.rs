@@ -0,0 +1,46 @@
+use crate::error::fs::{OpenFileError, ReadFileError, ReadToStringError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("invalid minisign public key '{key}'")]
+pub struct ParseReleaseKeyError {
+    pub key: String,
+    pub source: minisign_verify::Error,
+}
+
+#[derive(Error, Debug)]
+#[error("signature verification is enabled but no trusted keys remain: add a key to trusted_keys in the settings, or set verify_signatures to false")]
+pub struct NoTrustedKeysError;
+
+#[derive(Error, Debug)]
+pub enum VerifySignatureError {
+    #[error("signature {name} does not match")]
+    BadSignature {
+        name: String,
+        source: minisign_verify::Error,
+    },
+
+    #[error("failed to parse signature {name}")]
+    ParseSignature {
+        name: String,
+        source: minisign_verify::Error,
+    },
+
+    #[error("signature {name} is not from a trusted key")]
+    UntrustedKey { name: String },
+}
+
+#[derive(Error, Debug)]
+pub enum VerifyFileSignatureError {
+    #[error(transparent)]
+    OpenFile(#[from] OpenFileError),
+
+    #[error(transparent)]
+    ReadFile(#[from] ReadFileError),
+
+    #[error(transparent)]
+    ReadSignature(#[from] ReadToStringError),
+
+    #[error(transparent)]
+    VerifySignature(#[from] VerifySignatureError),
+}
@@ -0,0 +1,5 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("invalid target triple '{0}': expected one such as x86_64-unknown-linux-gnu")]
+pub struct ParseTargetError(pub String);
@@ -1,7 +1,8 @@
 use crate::error::fs::{
     AppendToFileError, CanonicalizePathError, CopyFileError, CreateDirAllError, CreateFileError,
-    OpenFileError, ReadFileError, ReadMetadataError, ReadToStringError, RemoveDirAllError,
-    RemoveFileError, RenameError, SetPermissionsError, SyncDataError, WriteFileError,
+    OpenFileError, ReadDirError, ReadFileError, ReadMetadataError, ReadToStringError,
+    RemoveDirAllError, RemoveFileError, RenameError, SetPermissionsError, SyncDataError,
+    WriteFileError,
 };
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -77,6 +78,13 @@ pub fn read(path: &Path) -> Result<Vec<u8>, ReadFileError> {
     })
 }
 
+pub fn read_dir(path: &Path) -> Result<std::fs::ReadDir, ReadDirError> {
+    std::fs::read_dir(path).map_err(|source| ReadDirError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
 pub fn read_to_string(path: &Path) -> Result<String, ReadToStringError> {
     std::fs::read_to_string(path).map_err(|source| ReadToStringError {
         path: path.to_path_buf(),
@@ -123,6 +131,37 @@ pub fn set_permissions(
     })
 }
 
+// Flushes a file, or a directory's entries, to disk
+pub fn sync(path: &Path) -> Result<(), SyncDataError> {
+    std::fs::File::open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(|source| SyncDataError {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+// Flushes a directory tree to disk, so that once it is renamed into place,
+// a crash can't leave it with missing or partly written files.
+pub fn sync_tree(path: &Path) -> Result<(), SyncDataError> {
+    let sync_error = |source| SyncDataError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file_type = std::fs::symlink_metadata(path)
+        .map_err(sync_error)?
+        .file_type();
+    if file_type.is_symlink() {
+        return Ok(());
+    }
+    if file_type.is_dir() {
+        for entry in std::fs::read_dir(path).map_err(sync_error)? {
+            sync_tree(&entry.map_err(sync_error)?.path())?;
+        }
+    }
+    sync(path)
+}
+
 pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<(), WriteFileError> {
     std::fs::write(path.as_ref(), contents).map_err(|source| WriteFileError {
         path: path.as_ref().to_path_buf(),
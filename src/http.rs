@@ -0,0 +1,107 @@
+use crate::error::http::{
+    BuildHttpClientError,
+    BuildHttpClientError::{
+        Build, InvalidProxy, NoCaCertificates, ParseCaCertificates, ParseClientCertificate,
+    },
+};
+use crate::error::reqwest::{StatusError, WrappedReqwestError};
+use crate::fs::read;
+use crate::retry::RetryPolicy;
+use crate::settings::Settings;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Certificate, Client, Identity, NoProxy, Proxy, Response};
+use std::ops::Deref;
+use std::time::{Duration, SystemTime};
+
+// A client, and how to retry the requests it makes
+pub struct HttpClient {
+    client: Client,
+    pub retry_policy: RetryPolicy,
+}
+
+impl Deref for HttpClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+// The client for every request that dfxvm makes.  Without a proxy in the
+// settings, reqwest uses the HTTPS_PROXY, HTTP_PROXY, ALL_PROXY and NO_PROXY
+// environment variables.
+pub fn client(settings: &Settings) -> Result<HttpClient, BuildHttpClientError> {
+    let mut builder = Client::builder();
+
+    if let Some(url) = settings.proxy() {
+        let proxy = Proxy::all(url).map_err(|source| InvalidProxy {
+            url: url.to_string(),
+            source: WrappedReqwestError(source),
+        })?;
+        let no_proxy = settings.no_proxy().and_then(NoProxy::from_string);
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+
+    // in addition to the system's root certificates, such as for a
+    // corporate proxy that intercepts TLS
+    if let Some(path) = settings.ca_certificates() {
+        let certificates =
+            Certificate::from_pem_bundle(&read(path)?).map_err(|source| ParseCaCertificates {
+                path: path.to_path_buf(),
+                source: WrappedReqwestError(source),
+            })?;
+        if certificates.is_empty() {
+            return Err(NoCaCertificates {
+                path: path.to_path_buf(),
+            });
+        }
+        builder = builder.tls_certs_merge(certificates);
+    }
+
+    // the certificate chain and private key may be in one file, or in two
+    if let Some(path) = settings.client_certificate() {
+        let mut pem = read(path)?;
+        if let Some(key_path) = settings.client_key() {
+            pem.push(b'\n');
+            pem.extend(read(key_path)?);
+        }
+        let identity = Identity::from_pem(&pem).map_err(|source| ParseClientCertificate {
+            path: path.to_path_buf(),
+            source: WrappedReqwestError(source),
+        })?;
+        builder = builder.identity(identity);
+    }
+
+    if let Some(timeout) = settings.connect_timeout() {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = settings.read_timeout() {
+        builder = builder.read_timeout(timeout);
+    }
+
+    let client = builder.build().map_err(|e| Build(WrappedReqwestError(e)))?;
+    Ok(HttpClient {
+        client,
+        retry_policy: RetryPolicy::new(settings),
+    })
+}
+
+// Like Response::error_for_status, but keeps how long the server asked to
+// wait before retrying
+pub fn error_for_status(res: Response) -> Result<Response, StatusError> {
+    let retry_after = retry_after(&res);
+    res.error_for_status().map_err(|e| StatusError {
+        error: WrappedReqwestError(e),
+        retry_after,
+    })
+}
+
+// Retry-After is either a number of seconds or an http date
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
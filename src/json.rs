@@ -1,19 +1,21 @@
 use crate::error::{
     json::{
         FetchJsonDocError,
-        FetchJsonDocError::{Get, Parse, ReadBytes, Status},
+        FetchJsonDocError::{Get, NoSignature, Parse, ReadBytes, Status},
         LoadJsonFileError, SaveJsonFileError,
     },
     reqwest::WrappedReqwestError,
-    Retryable,
 };
 use crate::fs::read;
+use crate::http;
+use crate::http::error_for_status;
 use crate::log::log_error;
-use backon::{ExponentialBuilder, Retryable as _};
-use reqwest::{Client, Url};
+use crate::settings::Settings;
+use crate::signature::{signature_url, TrustedKeys};
+use reqwest::{Client, StatusCode, Url};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn load_json_file<T: for<'a> serde::de::Deserialize<'a>>(
     path: &Path,
@@ -32,39 +34,74 @@ pub fn save_json_file<T: Serialize>(path: &Path, value: &T) -> Result<(), SaveJs
             path: path.to_path_buf(),
             source,
         })?;
-    crate::fs::write(path, content)?;
+    // write to a temporary file and rename it into place, so that readers
+    // never see a partially written file
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".tmp-{}", std::process::id()));
+    let temp_path = PathBuf::from(temp_path);
+    crate::fs::write(&temp_path, content)?;
+    crate::fs::rename(&temp_path, path)?;
     Ok(())
 }
 
-pub async fn fetch_json<T: DeserializeOwned>(url: &Url) -> Result<T, FetchJsonDocError> {
-    let client = Client::new();
+// If there are trusted keys, the document must have a minisign signature
+// next to it, with .minisig appended to its url.
+pub async fn fetch_json<T: DeserializeOwned>(
+    url: &Url,
+    trusted_keys: Option<&TrustedKeys>,
+    settings: &Settings,
+) -> Result<T, FetchJsonDocError> {
+    let client = http::client(settings)?;
     let notify = |err: &FetchJsonDocError, dur: std::time::Duration| {
         log_error(err);
         err!("retry in {dur:?}");
     };
 
-    (|| async { attempt_fetch_json(&client, url.clone()).await })
-        .retry(&ExponentialBuilder::default())
-        .when(|e| e.is_retryable())
-        .notify(notify)
+    client
+        .retry_policy
+        .retry(
+            || async { attempt_fetch_json(&client, url.clone(), trusted_keys).await },
+            notify,
+        )
         .await
 }
 
 async fn attempt_fetch_json<T: DeserializeOwned>(
     client: &Client,
     url: Url,
+    trusted_keys: Option<&TrustedKeys>,
 ) -> Result<T, FetchJsonDocError> {
     let response = client
-        .get(url)
+        .get(url.clone())
         .send()
         .await
-        .map_err(|e| Get(WrappedReqwestError(e)))?
-        .error_for_status()
-        .map_err(|e| Status(WrappedReqwestError(e)))?;
+        .map_err(|e| Get(WrappedReqwestError(e)))?;
+    let response = error_for_status(response).map_err(Status)?;
     let bytes = response
         .bytes()
         .await
         .map_err(|e| ReadBytes(WrappedReqwestError(e)))?;
+    if let Some(trusted_keys) = trusted_keys {
+        let signature_url = signature_url(&url);
+        let signature = fetch_signature(client, &signature_url).await?;
+        trusted_keys.verify(&bytes, &signature, signature_url.as_str())?;
+    }
     let doc = serde_json::from_slice(&bytes).map_err(Parse)?;
     Ok(doc)
 }
+
+async fn fetch_signature(client: &Client, url: &Url) -> Result<String, FetchJsonDocError> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| Get(WrappedReqwestError(e)))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(NoSignature { url: url.clone() });
+    }
+    error_for_status(response)
+        .map_err(Status)?
+        .text()
+        .await
+        .map_err(|e| ReadBytes(WrappedReqwestError(e)))
+}
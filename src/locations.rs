@@ -1,3 +1,4 @@
+use crate::dfx::Toolchain;
 use crate::env::home_dir;
 use crate::error::env::NoHomeDirectoryError;
 use directories::ProjectDirs;
@@ -6,6 +7,7 @@ use std::path::{Path, PathBuf};
 
 const SETTINGS_FILENAME: &str = "version-manager.json";
 
+#[derive(Clone)]
 pub struct Locations {
     data_local_dir: PathBuf,
     versions_dir: PathBuf,
@@ -22,8 +24,13 @@ impl Locations {
         self.versions_dir().join(version.to_string())
     }
 
-    pub fn dfx_bin_path(&self, version: &Version) -> PathBuf {
-        self.version_dir(version).join("dfx")
+    // A release's version directory, or the directory for a linked toolchain
+    pub fn toolchain_dir(&self, toolchain: &Toolchain) -> PathBuf {
+        self.versions_dir().join(toolchain.to_string())
+    }
+
+    pub fn dfx_bin_path(&self, toolchain: &Toolchain) -> PathBuf {
+        self.toolchain_dir(toolchain).join("dfx")
     }
 
     pub fn self_update_path(&self) -> PathBuf {
@@ -48,10 +55,60 @@ impl Locations {
         self.bin_dir().join("dfxvm")
     }
 
+    pub fn cache_dir(&self) -> PathBuf {
+        self.data_local_dir.join("cache")
+    }
+
+    pub fn download_cache_dir(&self) -> PathBuf {
+        self.cache_dir().join("downloads")
+    }
+
+    pub fn tags_cache_path(&self) -> PathBuf {
+        self.cache_dir().join("tags.json")
+    }
+
     pub fn settings_path(&self) -> PathBuf {
         self.config_dir.join(SETTINGS_FILENAME)
     }
 
+    pub fn locks_dir(&self) -> PathBuf {
+        self.data_local_dir.join("locks")
+    }
+
+    // Held while installing, linking or uninstalling a toolchain
+    pub fn toolchain_lock_path(&self, toolchain: &Toolchain) -> PathBuf {
+        self.locks_dir().join(format!("dfx-{toolchain}.lock"))
+    }
+
+    // Held while removing temporary directories that belong to no toolchain
+    pub fn versions_lock_path(&self) -> PathBuf {
+        self.locks_dir().join("versions.lock")
+    }
+
+    // Held while changing the settings file
+    pub fn settings_lock_path(&self) -> PathBuf {
+        self.locks_dir().join("settings.lock")
+    }
+
+    pub fn self_update_lock_path(&self) -> PathBuf {
+        self.locks_dir().join("self-update.lock")
+    }
+
+    // In-progress installs and uninstalls, for recovery if they are interrupted
+    pub fn journal_dir(&self) -> PathBuf {
+        self.data_local_dir.join("journal")
+    }
+
+    // Where a toolchain is extracted before it is renamed into place
+    pub fn install_staging_dir(&self, toolchain: &Toolchain) -> PathBuf {
+        self.versions_dir().join(format!(".install-{toolchain}"))
+    }
+
+    // Where a toolchain is renamed to before it is removed
+    pub fn uninstall_staging_dir(&self, toolchain: &Toolchain) -> PathBuf {
+        self.versions_dir().join(format!(".uninstall-{toolchain}"))
+    }
+
     pub fn env_path(&self) -> PathBuf {
         self.data_local_dir.join("env")
     }
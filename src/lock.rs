@@ -0,0 +1,138 @@
+use crate::error::{
+    fs::OpenFileError,
+    lock::{AcquireLockError, AcquireLockError::Lock},
+};
+use crate::fs::create_dir_all;
+use indicatif::MultiProgress;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// How often a task waiting for a lock checks whether it is free
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// An advisory lock on a file, shared by all dfxvm processes and released
+// when dropped.  The lock file records the pid of the process holding it,
+// so that a process waiting for the lock can say what it is waiting for.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub fn acquire(path: &Path) -> Result<Self, AcquireLockError> {
+        let mut file = open_lock_file(path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                log_waiting(&mut file, path);
+                file.lock().map_err(|source| Lock {
+                    path: path.to_path_buf(),
+                    source,
+                })?;
+            }
+            Err(TryLockError::Error(source)) => {
+                return Err(Lock {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        }
+
+        // the pid is only informational, so failing to record it is not an error
+        let _ = record_pid(&mut file);
+
+        Ok(Self { _file: file })
+    }
+
+    // Waits for the lock without blocking the thread, so that other tasks on
+    // it, such as downloads of other versions, keep going.
+    pub async fn acquire_async(
+        path: &Path,
+        progress: &MultiProgress,
+    ) -> Result<Self, AcquireLockError> {
+        let mut file = open_lock_file(path)?;
+
+        let mut waiting = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    if !waiting {
+                        progress.suspend(|| log_waiting(&mut file, path));
+                        waiting = true;
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                Err(TryLockError::Error(source)) => {
+                    return Err(Lock {
+                        path: path.to_path_buf(),
+                        source,
+                    })
+                }
+            }
+        }
+
+        let _ = record_pid(&mut file);
+
+        Ok(Self { _file: file })
+    }
+
+    // Acquires the lock if no other process holds it
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>, AcquireLockError> {
+        let mut file = open_lock_file(path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(source)) => {
+                return Err(Lock {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        }
+
+        let _ = record_pid(&mut file);
+
+        Ok(Some(Self { _file: file }))
+    }
+}
+
+fn open_lock_file(path: &Path) -> Result<File, AcquireLockError> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|source| OpenFileError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    Ok(file)
+}
+
+fn log_waiting(file: &mut File, path: &Path) {
+    match holder_pid(file) {
+        Some(pid) => info!("waiting for lock held by pid {pid}"),
+        None => info!("waiting for lock on {}", path.display()),
+    }
+}
+
+fn holder_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn record_pid(file: &mut File) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    write!(file, "{}", std::process::id())?;
+    file.flush()
+}
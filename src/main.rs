@@ -5,6 +5,7 @@ use std::process::ExitCode;
 #[macro_use]
 mod log;
 
+mod archive;
 mod cli;
 mod dfx;
 mod dfxvm;
@@ -14,11 +15,17 @@ mod download;
 mod env;
 mod error;
 mod fs;
+mod http;
 mod installation;
 mod json;
 mod locations;
+mod lock;
+mod mirror;
+mod retry;
 mod settings;
+mod signature;
 mod style;
+mod target;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> ExitCode {
@@ -0,0 +1,39 @@
+use crate::error::MirrorFailure;
+use crate::log::log_error;
+use indicatif::MultiProgress;
+use std::error::Error;
+use std::fmt::Display;
+use std::future::Future;
+
+// Tries the mirrors in order until one succeeds.  Falls through to the next
+// mirror on a failure that another mirror might not have, such as a connection
+// failure, a server error, a missing file or a checksum mismatch.  Returns the
+// error from the last mirror tried.
+pub async fn try_mirrors<'a, M, T, E, F, Fut>(
+    mirrors: &'a [M],
+    progress: &MultiProgress,
+    mut attempt: F,
+) -> Result<T, E>
+where
+    M: Display,
+    E: Error + MirrorFailure,
+    F: FnMut(&'a M) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut mirrors = mirrors.iter().peekable();
+    loop {
+        let mirror = mirrors.next().expect("at least one mirror");
+        match attempt(mirror).await {
+            Err(e) if e.is_mirror_failure() => {
+                let Some(next) = mirrors.peek() else {
+                    return Err(e);
+                };
+                progress.suspend(|| {
+                    log_error(&e);
+                    info!("trying the next mirror, {next}");
+                });
+            }
+            result => return result,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::Retryable;
+use crate::settings::Settings;
+use backon::{ExponentialBuilder, Retryable as _};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// From --retries or --no-retry, which take precedence over the retries setting
+static RETRIES_OVERRIDE: OnceLock<u32> = OnceLock::new();
+
+pub fn override_retries(retries: u32) {
+    let _ = RETRIES_OVERRIDE.set(retries);
+}
+
+// How many times, and how soon, to retry a failed request
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    retries: u32,
+    base_delay: Duration,
+    deadline: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            retries: RETRIES_OVERRIDE
+                .get()
+                .copied()
+                .unwrap_or_else(|| settings.retries()),
+            base_delay: settings.retry_base_delay(),
+            deadline: settings.retry_deadline(),
+        }
+    }
+
+    // Retries with exponential backoff from the base delay, waiting at least as
+    // long as a Retry-After header asks.  Gives up rather than wait past the
+    // deadline, counted from the first attempt.
+    pub async fn retry<T, E, F, Fut>(&self, f: F, notify: impl Fn(&E, Duration)) -> Result<T, E>
+    where
+        E: Retryable,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let started = Instant::now();
+        let backoff = ExponentialBuilder::default()
+            .with_min_delay(self.base_delay)
+            .with_max_times(self.retries as usize);
+
+        f.retry(&backoff)
+            .when(|e| e.is_retryable())
+            .adjust(|e, delay| {
+                let delay = delay?.max(e.retry_after().unwrap_or_default());
+                (started.elapsed() + delay <= self.deadline).then_some(delay)
+            })
+            .notify(|e, delay| notify(e, delay))
+            .await
+    }
+}
@@ -1,45 +1,215 @@
-use crate::error::json::{LoadJsonFileError, SaveJsonFileError};
+use crate::archive::ArchiveFormat;
+use crate::dfx::{DfxVersionSpec, Toolchain};
+use crate::error::{
+    json::{LoadJsonFileError, SaveJsonFileError},
+    signature::NoTrustedKeysError,
+};
 use crate::json::{load_json_file, save_json_file};
+use crate::signature::{ReleaseKey, TrustedKeys};
+use crate::target::Target;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 const DEFAULT_DOWNLOAD_URL_TEMPLATE: &str =
     "https://github.com/dfinity/sdk/releases/download/{{version}}/{{basename}}.{{archive-format}}";
 const DEFAULT_DFXVM_LATEST_DOWNLOAD_ROOT_URL: &str =
     "https://github.com/dfinity/dfxvm/releases/latest/download";
 const DEFAULT_MANIFEST_URL: &str = "https://sdk.dfinity.org/manifest.json";
+const DEFAULT_DOWNLOAD_CACHE_LIMIT_MB: u64 = 1024;
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 1000;
+const DEFAULT_RETRY_DEADLINE_SECS: u64 = 300;
+
+// Whether the dfx proxy installs a missing dfx version before running it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoInstall {
+    #[default]
+    Never,
+    Prompt,
+    Always,
+}
+
+impl FromStr for AutoInstall {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(AutoInstall::Never),
+            "prompt" => Ok(AutoInstall::Prompt),
+            "always" => Ok(AutoInstall::Always),
+            _ => Err(()),
+        }
+    }
+}
+
+// A url, or a list of mirrors to try in order
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Mirrors {
+    One(String),
+    List(Vec<String>),
+}
+
+impl Mirrors {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            Mirrors::One(url) => vec![url.clone()],
+            Mirrors::List(urls) => urls.clone(),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Settings {
+    // formats to download releases in, most preferred first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_formats: Option<Vec<ArchiveFormat>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_install: Option<AutoInstall>,
+
+    // PEM bundle of root certificates to trust in addition to the system's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ca_certificates: Option<PathBuf>,
+
+    // PEM client certificate chain for mutual TLS, with its key unless client_key is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_certificate: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_key: Option<PathBuf>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub default_version: Option<Version>,
+    connect_timeout_secs: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_version: Option<Toolchain>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     dfxvm_latest_download_root: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    download_url_template: Option<String>,
+    download_cache_limit_mb: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_connections: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_url_template: Option<Mirrors>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest_url: Option<Mirrors>,
+
+    // comma-separated hosts and domains to reach without the proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_proxy: Option<String>,
+
+    // proxy for all requests, taking precedence over HTTPS_PROXY and the like
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    manifest_url: Option<String>,
+    read_timeout_secs: Option<u64>,
+
+    // how many times to retry a failed request, after the first attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+
+    // the delay before the first retry, which doubles for each retry after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_base_delay_ms: Option<u64>,
+
+    // no retry starts later than this after the first attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_deadline_secs: Option<u64>,
+
+    // keys that are no longer trusted, even if built in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    revoked_keys: Vec<ReleaseKey>,
+
+    // keys trusted in addition to the built-in keys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trusted_keys: Vec<ReleaseKey>,
+
+    // the target to install dfx releases for, if not this machine's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<Target>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verify_signatures: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warm_cache: Option<bool>,
+
+    // versioned proxy name (dfx-<something>) -> dfx version it runs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shims: BTreeMap<String, DfxVersionSpec>,
+
+    // directory (canonical path) -> dfx version to use in it and its subdirectories
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<PathBuf, Version>,
 
     #[serde(flatten)]
     extra: Value,
 }
 
 impl Settings {
+    pub fn archive_formats(&self) -> Vec<ArchiveFormat> {
+        self.archive_formats
+            .clone()
+            .filter(|formats| !formats.is_empty())
+            .unwrap_or_else(|| ArchiveFormat::PREFERRED.to_vec())
+    }
+
+    pub fn auto_install(&self) -> AutoInstall {
+        self.auto_install.unwrap_or_default()
+    }
+
+    pub fn ca_certificates(&self) -> Option<&Path> {
+        self.ca_certificates.as_deref()
+    }
+
+    pub fn client_certificate(&self) -> Option<&Path> {
+        self.client_certificate.as_deref()
+    }
+
+    pub fn client_key(&self) -> Option<&Path> {
+        self.client_key.as_deref()
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_secs.map(Duration::from_secs)
+    }
+
     pub fn dfxvm_latest_download_root(&self) -> String {
         self.dfxvm_latest_download_root
             .clone()
             .unwrap_or_else(|| DEFAULT_DFXVM_LATEST_DOWNLOAD_ROOT_URL.to_string())
     }
 
-    pub fn download_url_template(&self) -> String {
+    pub fn download_cache_limit_bytes(&self) -> u64 {
+        self.download_cache_limit_mb
+            .unwrap_or(DEFAULT_DOWNLOAD_CACHE_LIMIT_MB)
+            .saturating_mul(1024 * 1024)
+    }
+
+    pub fn download_connections(&self) -> u32 {
+        self.download_connections.unwrap_or(1).max(1)
+    }
+
+    // The release download url templates, one per mirror, in the order to try them
+    pub fn download_url_templates(&self) -> Vec<String> {
         self.download_url_template
-            .clone()
-            .unwrap_or_else(|| DEFAULT_DOWNLOAD_URL_TEMPLATE.to_string())
+            .as_ref()
+            .map(Mirrors::to_vec)
+            .filter(|templates| !templates.is_empty())
+            .unwrap_or_else(|| vec![DEFAULT_DOWNLOAD_URL_TEMPLATE.to_string()])
     }
 
     pub fn load_or_default(path: &Path) -> Result<Self, LoadJsonFileError> {
@@ -50,10 +220,82 @@ impl Settings {
         }
     }
 
-    pub fn manifest_url(&self) -> String {
+    // The manifest urls, one per mirror, in the order to try them
+    pub fn manifest_urls(&self) -> Vec<String> {
         self.manifest_url
+            .as_ref()
+            .map(Mirrors::to_vec)
+            .filter(|urls| !urls.is_empty())
+            .unwrap_or_else(|| vec![DEFAULT_MANIFEST_URL.to_string()])
+    }
+
+    pub fn no_proxy(&self) -> Option<&str> {
+        self.no_proxy.as_deref()
+    }
+
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_RETRIES)
+    }
+
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(
+            self.retry_base_delay_ms
+                .unwrap_or(DEFAULT_RETRY_BASE_DELAY_MS),
+        )
+    }
+
+    pub fn retry_deadline(&self) -> Duration {
+        Duration::from_secs(
+            self.retry_deadline_secs
+                .unwrap_or(DEFAULT_RETRY_DEADLINE_SECS),
+        )
+    }
+
+    // The keys to verify signatures with, or None if signatures are not verified.
+    // Unless verify_signatures says otherwise, signatures are verified if any keys
+    // are built in or added in settings.  With verification on and no keys left,
+    // such as after revoking the only key, nothing can be verified, which is an
+    // error rather than a reason to skip verification.
+    pub fn trusted_keys(&self) -> Result<Option<TrustedKeys>, NoTrustedKeysError> {
+        let verify = self
+            .verify_signatures
+            .unwrap_or_else(|| TrustedKeys::any_configured(&self.trusted_keys));
+        if !verify {
+            return Ok(None);
+        }
+        let keys = TrustedKeys::new(&self.trusted_keys, &self.revoked_keys);
+        if keys.is_empty() {
+            return Err(NoTrustedKeysError);
+        }
+        Ok(Some(keys))
+    }
+
+    pub fn target(&self, version: &Version) -> Target {
+        self.target
             .clone()
-            .unwrap_or_else(|| DEFAULT_MANIFEST_URL.to_string())
+            .unwrap_or_else(|| Target::host_for_dfx(version))
+    }
+
+    // Whether to run `dfx cache install` after installing a dfx version
+    pub fn warm_cache(&self) -> bool {
+        self.warm_cache.unwrap_or(false)
+    }
+
+    // The override for the closest of dir and its ancestors, if any
+    pub fn override_for(&self, dir: &Path) -> Option<(&Path, &Version)> {
+        dir.ancestors().find_map(|ancestor| {
+            self.overrides
+                .get_key_value(ancestor)
+                .map(|(path, version)| (path.as_path(), version))
+        })
     }
 
     pub fn save(&self, path: &Path) -> Result<(), SaveJsonFileError> {
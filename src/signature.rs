@@ -0,0 +1,167 @@
+use crate::error::{
+    fs::ReadFileError,
+    signature::{
+        ParseReleaseKeyError, VerifyFileSignatureError,
+        VerifySignatureError::{self, BadSignature, ParseSignature, UntrustedKey},
+    },
+};
+use crate::fs::{open_file, read_to_string};
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use url::Url;
+
+// Minisign public keys that sign dfx and dfxvm releases, as in the second
+// line of a minisign.pub file.  To rotate a key, release a dfxvm that trusts
+// both the old and the new key, sign releases with the new key, and remove
+// the old key once no supported release needs it.
+const BUILTIN_RELEASE_KEYS: &[&str] = &[];
+
+// A minisign public key, as in the second line of a minisign.pub file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReleaseKey {
+    encoded: String,
+    key: PublicKey,
+}
+
+impl FromStr for ReleaseKey {
+    type Err = ParseReleaseKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s.trim().to_string();
+        let key = PublicKey::from_base64(&encoded).map_err(|source| ParseReleaseKeyError {
+            key: encoded.clone(),
+            source,
+        })?;
+        Ok(Self { encoded, key })
+    }
+}
+
+impl Serialize for ReleaseKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encoded)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// The keys whose signatures dfxvm accepts: the built-in keys and any added
+// in settings, less any revoked in settings.
+#[derive(Clone)]
+pub struct TrustedKeys(Vec<PublicKey>);
+
+impl TrustedKeys {
+    pub fn new(added: &[ReleaseKey], revoked: &[ReleaseKey]) -> Self {
+        let builtin = BUILTIN_RELEASE_KEYS
+            .iter()
+            .map(|s| s.parse::<ReleaseKey>().expect("valid built-in release key"));
+        let mut keys: Vec<PublicKey> = vec![];
+        for key in builtin.chain(added.iter().cloned()) {
+            if !revoked.contains(&key) && !keys.contains(&key.key) {
+                keys.push(key.key);
+            }
+        }
+        Self(keys)
+    }
+
+    // Whether any keys are built in or added, whether or not they are revoked
+    pub fn any_configured(added: &[ReleaseKey]) -> bool {
+        !BUILTIN_RELEASE_KEYS.is_empty() || !added.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Verifies a minisign signature of contents, where name identifies the
+    // signature in errors, such as its url.
+    pub fn verify(
+        &self,
+        contents: &[u8],
+        signature: &str,
+        name: &str,
+    ) -> Result<(), VerifySignatureError> {
+        let signature = decode_signature(signature, name)?;
+        let key = self.signing_key(&signature, name)?;
+        key.verify(contents, &signature, false)
+            .map_err(|source| BadSignature {
+                name: name.to_string(),
+                source,
+            })?;
+        info!("verified signature {name}");
+        Ok(())
+    }
+
+    // Verifies a file against a minisign signature file, without reading
+    // the whole file into memory.  Unlike verify, this doesn't log, so that
+    // it can run while progress bars are drawn.
+    pub fn verify_file(
+        &self,
+        path: &Path,
+        signature_path: &Path,
+    ) -> Result<(), VerifyFileSignatureError> {
+        let name = signature_path.display().to_string();
+        let signature = decode_signature(&read_to_string(signature_path)?, &name)?;
+        let key = self.signing_key(&signature, &name)?;
+        let bad_signature = |source| BadSignature {
+            name: name.clone(),
+            source,
+        };
+        let mut verifier = key.verify_stream(&signature).map_err(bad_signature)?;
+
+        let mut file = open_file(path)?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf).map_err(|source| ReadFileError {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            if n == 0 {
+                break;
+            }
+            verifier.update(&buf[..n]);
+        }
+        verifier.finalize().map_err(bad_signature)?;
+        Ok(())
+    }
+
+    // The trusted key with the signature's key id
+    fn signing_key(
+        &self,
+        signature: &Signature,
+        name: &str,
+    ) -> Result<&PublicKey, VerifySignatureError> {
+        self.0
+            .iter()
+            .find(|key| {
+                !matches!(
+                    key.verify_stream(signature),
+                    Err(minisign_verify::Error::UnexpectedKeyId)
+                )
+            })
+            .ok_or_else(|| UntrustedKey {
+                name: name.to_string(),
+            })
+    }
+}
+
+fn decode_signature(signature: &str, name: &str) -> Result<Signature, VerifySignatureError> {
+    Signature::decode(signature).map_err(|source| ParseSignature {
+        name: name.to_string(),
+        source,
+    })
+}
+
+// A file's signature is next to it, with .minisig appended to its name
+pub fn signature_url(url: &Url) -> Url {
+    let mut signature_url = url.clone();
+    signature_url.set_path(&format!("{}.minisig", url.path()));
+    signature_url
+}
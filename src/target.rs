@@ -0,0 +1,104 @@
+use crate::error::target::ParseTargetError;
+use semver::Version;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// A target triple that releases are built for, such as x86_64-unknown-linux-gnu
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Target(String);
+
+impl Target {
+    // The target of this machine
+    pub fn host() -> Self {
+        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+        let triple = "aarch64-apple-darwin";
+        #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+        let triple = "x86_64-apple-darwin";
+        #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+        let triple = "aarch64-unknown-linux-gnu";
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        let triple = "x86_64-unknown-linux-gnu";
+        Self(triple.to_string())
+    }
+
+    // The target of the dfx release to install on this machine
+    #[allow(unused_variables)]
+    pub fn host_for_dfx(version: &Version) -> Self {
+        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+        {
+            // This is the first version that supports aarch64-apple-darwin.
+            // Earlier versions run under Rosetta.
+            let aarch64_apple_darwin_version = Version::parse("0.28.0-beta.1").unwrap();
+            if version < &aarch64_apple_darwin_version {
+                return Self("x86_64-apple-darwin".to_string());
+            }
+        }
+        Self::host()
+    }
+
+    pub fn arch(&self) -> &str {
+        self.0.split('-').next().unwrap_or_default()
+    }
+
+    pub fn os(&self) -> Option<&'static str> {
+        if self.0.contains("-linux") {
+            Some("linux")
+        } else if self.0.contains("-darwin") {
+            Some("macos")
+        } else {
+            None
+        }
+    }
+
+    // Whether binaries built for this target can run on this machine,
+    // including x86_64 macOS binaries on Apple silicon
+    pub fn runs_here(&self) -> bool {
+        let host = Self::host();
+        self.os().is_some()
+            && self.os() == host.os()
+            && (self.arch() == host.arch()
+                || (host.os() == Some("macos")
+                    && host.arch() == "aarch64"
+                    && self.arch() == "x86_64"))
+    }
+}
+
+impl FromStr for Target {
+    type Err = ParseTargetError;
+
+    // arch-vendor-os, with an optional environment such as gnu or musl
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('-').collect();
+        let valid = (3..=4).contains(&parts.len())
+            && parts.iter().all(|part| {
+                !part.is_empty()
+                    && part.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.'
+                    })
+            });
+        if !valid {
+            return Err(ParseTargetError(s.to_string()));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Target {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Target {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
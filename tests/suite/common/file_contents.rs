@@ -37,6 +37,31 @@ pub fn manifest_json(latest: &str) -> String {
     .to_string()
 }
 
+pub fn manifest_json_with_tags(tags: &[(&str, &str)]) -> String {
+    let tags: serde_json::Map<String, serde_json::Value> = tags
+        .iter()
+        .map(|(tag, version)| (tag.to_string(), json!(version)))
+        .collect();
+    json!({
+        "tags": tags,
+        "versions": [
+            "0.5.0",
+            "0.5.2"
+        ]
+    })
+    .to_string()
+}
+
+pub fn manifest_json_with_versions(latest: &str, versions: &[&str]) -> String {
+    json!({
+        "tags": {
+            "latest": latest
+        },
+        "versions": versions
+    })
+    .to_string()
+}
+
 pub fn dist_manifest_json(latest: &str) -> String {
     json!({
         "releases": [
@@ -49,18 +74,23 @@ pub fn dist_manifest_json(latest: &str) -> String {
     .to_string()
 }
 
-pub fn dfx_tarball(version: &Version, contents: &[u8]) -> Vec<u8> {
+pub fn dfx_tarball(version: &Version, contents: &[u8], mode: u32) -> Vec<u8> {
+    dfx_archive(version, contents, mode, "tar.gz")
+}
+
+pub fn dfx_archive(version: &Version, contents: &[u8], mode: u32, archive_format: &str) -> Vec<u8> {
     let dirname = ReleaseAsset::dfx_tarball_basename(version);
     let include_docs = false;
 
-    tool_tarball("dfx", dirname, contents, include_docs)
+    let tar = tool_tar("dfx", dirname, contents, mode, include_docs);
+    compress(&tar, archive_format)
 }
 
 pub fn dfxvm_tarball(contents: &[u8]) -> Vec<u8> {
     let dirname = ReleaseAsset::dfxvm_tarball_basename();
     let include_docs = true;
 
-    tool_tarball("dfxvm", &dirname, contents, include_docs)
+    tool_tarball("dfxvm", &dirname, contents, 0o755, include_docs)
 }
 
 // dfxvm tarball looks like:
@@ -71,8 +101,18 @@ pub fn dfxvm_tarball(contents: &[u8]) -> Vec<u8> {
 // -rw-r--r--  0 501    20      11357 Dec 19 11:21 dfxvm-aarch64-apple-darwin/LICENSE
 // -rwxr-xr-x  0 501    20    5747075 Dec 19 11:24 dfxvm-aarch64-apple-darwin/dfxvm
 
-pub fn tool_tarball(tool: &str, dirname: &str, contents: &[u8], include_docs: bool) -> Vec<u8> {
-    let tar_buffer = Vec::new();
+pub fn tool_tarball(
+    tool: &str,
+    dirname: &str,
+    contents: &[u8],
+    mode: u32,
+    include_docs: bool,
+) -> Vec<u8> {
+    let tar = tool_tar(tool, dirname, contents, mode, include_docs);
+    compress(&tar, "tar.gz")
+}
+
+fn tool_tar(tool: &str, dirname: &str, contents: &[u8], mode: u32, include_docs: bool) -> Vec<u8> {
     let mut tar = Builder::new(Vec::new());
 
     if include_docs {
@@ -80,12 +120,25 @@ pub fn tool_tarball(tool: &str, dirname: &str, contents: &[u8], include_docs: bo
         append_file(&mut tar, 0o644, dirname, "CHANGELOG.md", b"the changelog\n");
     }
     append_file(&mut tar, 0o644, dirname, "LICENSE", b"the license\n");
-    append_file(&mut tar, 0o755, dirname, tool, contents);
-
-    let mut gzipped = GzEncoder::new(tar_buffer, Compression::default());
-    gzipped.write_all(&tar.into_inner().unwrap()).unwrap();
+    append_file(&mut tar, mode, dirname, tool, contents);
+    tar.into_inner().unwrap()
+}
 
-    gzipped.finish().unwrap()
+fn compress(tar: &[u8], archive_format: &str) -> Vec<u8> {
+    match archive_format {
+        "tar.gz" => {
+            let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+            gzipped.write_all(tar).unwrap();
+            gzipped.finish().unwrap()
+        }
+        "tar.xz" => {
+            let mut xz = liblzma::write::XzEncoder::new(Vec::new(), 6);
+            xz.write_all(tar).unwrap();
+            xz.finish().unwrap()
+        }
+        "tar.zst" => zstd::encode_all(tar, 0).unwrap(),
+        other => panic!("unknown archive format {other}"),
+    }
 }
 
 fn append_file(
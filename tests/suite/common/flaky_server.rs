@@ -0,0 +1,122 @@
+use crate::common::release_server::parse_range;
+use crate::common::{ReleaseAsset, TempHomeDir};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+// A release server that drops the connection partway through the first
+// download of the tarball, which httptest can't do.
+pub struct FlakyServer {
+    url: String,
+    range_headers: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+struct State {
+    assets: Vec<ReleaseAsset>,
+    truncate_at: Option<usize>,
+    honor_ranges: bool,
+}
+
+impl FlakyServer {
+    pub fn start(
+        home_dir: &TempHomeDir,
+        tarball: &ReleaseAsset,
+        sha256: &ReleaseAsset,
+        truncate_at: usize,
+        honor_ranges: bool,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        home_dir.settings().write_download_url_template(&format!(
+            "{url}/any/arbitrary/path/{{{{version}}}}/{{{{basename}}}}.{{{{archive-format}}}}"
+        ));
+
+        let range_headers = Arc::new(Mutex::new(vec![]));
+        let mut state = State {
+            assets: vec![tarball.clone(), sha256.clone()],
+            truncate_at: Some(truncate_at),
+            honor_ranges,
+        };
+        let recorded = range_headers.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                if let Some((path, range)) = serve(stream, &mut state) {
+                    if path.ends_with(".tar.gz") {
+                        recorded.lock().unwrap().push(range);
+                    }
+                }
+            }
+        });
+        Self { url, range_headers }
+    }
+
+    pub fn url(&self, asset: &ReleaseAsset) -> String {
+        format!("{}{}", self.url, asset.url_path)
+    }
+
+    // The Range header of each request for the tarball
+    pub fn tarball_range_headers(&self) -> Vec<Option<String>> {
+        self.range_headers.lock().unwrap().clone()
+    }
+}
+
+fn serve(mut stream: TcpStream, state: &mut State) -> Option<(String, Option<String>)> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let path = request_line.split_whitespace().nth(1)?.to_string();
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let Some(asset) = state.assets.iter().find(|asset| asset.url_path == path) else {
+        let _ = stream
+            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        return Some((path, range));
+    };
+    let contents = &asset.contents;
+    let len = contents.len();
+    let is_tarball = path.ends_with(".tar.gz");
+
+    let requested = range
+        .as_deref()
+        .filter(|_| state.honor_ranges)
+        .and_then(|value| parse_range(value, len));
+    let (status, body, content_range) = match requested {
+        Some((start, end)) => (
+            "206 Partial Content",
+            &contents[start..=end],
+            Some(format!("bytes {start}-{end}/{len}")),
+        ),
+        None => ("200 OK", &contents[..], None),
+    };
+    let mut head = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n",
+        body.len()
+    );
+    if let Some(content_range) = content_range {
+        head.push_str(&format!("Content-Range: {content_range}\r\n"));
+    }
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes());
+
+    let body = match state.truncate_at.take_if(|_| is_tarball) {
+        Some(truncate_at) => &body[..truncate_at],
+        None => body,
+    };
+    let _ = stream.write_all(body);
+    let _ = stream.flush();
+    Some((path, range))
+}
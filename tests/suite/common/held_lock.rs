@@ -0,0 +1,51 @@
+use std::fs::{create_dir_all, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+// A lock held by the test, as though by another dfxvm process with the given pid
+pub struct HeldLock {
+    _file: File,
+}
+
+impl HeldLock {
+    pub fn acquire(path: &Path, pid: u32) -> Self {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        let mut file = File::create(path).unwrap();
+        file.lock().unwrap();
+        write!(file, "{pid}").unwrap();
+        Self { _file: file }
+    }
+
+    // Runs the command until it reports that it is waiting for a lock,
+    // then releases this lock and waits for the command to finish.
+    pub fn release_when_waited_for(self, command: Command) -> Output {
+        self.release_when_logged(command, "waiting for lock")
+    }
+
+    // Runs the command until it logs a line containing the message,
+    // then releases this lock and waits for the command to finish.
+    pub fn release_when_logged(self, mut command: Command, message: &str) -> Output {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = command.spawn().unwrap();
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+
+        let mut lines = String::new();
+        loop {
+            let mut line = String::new();
+            if stderr.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            lines.push_str(&line);
+            if line.contains(message) {
+                break;
+            }
+        }
+        drop(self);
+
+        stderr.read_to_string(&mut lines).unwrap();
+        let mut output = child.wait_with_output().unwrap();
+        output.stderr = lines.into_bytes();
+        output
+    }
+}
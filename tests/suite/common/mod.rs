@@ -1,15 +1,21 @@
 mod executable;
 pub mod file_contents;
+mod flaky_server;
+mod held_lock;
 pub mod paths;
 pub mod project_dirs;
 mod release_asset;
 mod release_server;
 mod settings;
+mod signing;
 mod temp_home_dir;
 
+pub use flaky_server::FlakyServer;
+pub use held_lock::HeldLock;
 pub use release_asset::ReleaseAsset;
-pub use release_server::ReleaseServer;
+pub use release_server::{RangeResponder, ReleaseServer};
 pub use settings::Settings;
+pub use signing::TestKey;
 pub use temp_home_dir::TempHomeDir;
 
 pub fn dfxvm_path() -> &'static str {
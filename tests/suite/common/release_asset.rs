@@ -1,6 +1,7 @@
 use crate::common::{
     file_contents,
-    file_contents::{bash_script, dfx_tarball},
+    file_contents::{bash_script, dfx_archive, dfx_tarball, tool_tarball},
+    TestKey,
 };
 use httptest::http::{response, Response};
 use semver::Version;
@@ -18,13 +19,47 @@ impl ReleaseAsset {
     }
 
     pub fn dfx_tarball_with_dfx_contents(version: &str, executable: &[u8]) -> ReleaseAsset {
+        Self::dfx_tarball_with_dfx_mode(version, executable, 0o755)
+    }
+
+    pub fn dfx_tarball_with_dfx_mode(version: &str, contents: &[u8], mode: u32) -> ReleaseAsset {
         let version = Version::parse(version).unwrap();
         let filename = Self::dfx_tarball_filename(&version).to_string();
 
         // must match the download_url_template in ReleaseServer::new
         let url_path = format!("/any/arbitrary/path/{version}/{filename}");
 
-        let contents = dfx_tarball(&version, executable);
+        let contents = dfx_tarball(&version, contents, mode);
+        ReleaseAsset {
+            url_path,
+            filename,
+            contents,
+        }
+    }
+
+    pub fn dfx_archive(version: &str, snippet: &str, archive_format: &str) -> ReleaseAsset {
+        let version = Version::parse(version).unwrap();
+        let basename = Self::dfx_tarball_basename(&version);
+        let filename = format!("{basename}.{archive_format}");
+        let url_path = format!("/any/arbitrary/path/{version}/{filename}");
+        let contents = dfx_archive(
+            &version,
+            bash_script(snippet).as_bytes(),
+            0o755,
+            archive_format,
+        );
+        ReleaseAsset {
+            url_path,
+            filename,
+            contents,
+        }
+    }
+
+    pub fn dfx_tarball_for_target(version: &str, target: &str, contents: &[u8]) -> ReleaseAsset {
+        let basename = format!("dfx-{target}");
+        let filename = format!("{basename}.tar.gz");
+        let url_path = format!("/any/arbitrary/path/{version}/{filename}");
+        let contents = tool_tarball("dfx", &basename, contents, 0o755, false);
         ReleaseAsset {
             url_path,
             filename,
@@ -45,6 +80,21 @@ impl ReleaseAsset {
         }
     }
 
+    // the checksum in the asset's .sha256 file
+    pub fn sha256_hex(&self) -> String {
+        let sha256 = Self::sha256(self);
+        let contents = String::from_utf8(sha256.contents).unwrap();
+        contents.split_whitespace().next().unwrap().to_string()
+    }
+
+    pub fn signature(asset: &ReleaseAsset, key: &TestKey) -> ReleaseAsset {
+        ReleaseAsset {
+            url_path: format!("{}.minisig", asset.url_path),
+            filename: format!("{}.minisig", asset.filename),
+            contents: key.sign(&asset.contents).into_bytes(),
+        }
+    }
+
     pub fn ok_response(&self) -> Response<Vec<u8>> {
         response::Builder::new()
             .status(200)
@@ -71,6 +121,14 @@ impl ReleaseAsset {
         return "dfx-aarch64-unknown-linux-gnu";
     }
 
+    // the target that dfxvm installs a version for on this machine
+    pub fn host_target(version: &str) -> &'static str {
+        let version = Version::parse(version).unwrap();
+        Self::dfx_tarball_basename(&version)
+            .strip_prefix("dfx-")
+            .unwrap()
+    }
+
     pub fn dfx_tarball_filename(version: &Version) -> String {
         let basename = Self::dfx_tarball_basename(version);
         let archive_format = "tar.gz";
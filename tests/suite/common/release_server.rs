@@ -1,7 +1,12 @@
 use crate::common::file_contents::manifest_json;
-use crate::common::{ReleaseAsset, TempHomeDir};
-use httptest::http::response;
-use httptest::{matchers::request, responders::status_code, Expectation, Server};
+use crate::common::{ReleaseAsset, TempHomeDir, TestKey};
+use httptest::bytes::Bytes;
+use httptest::http::{response, Request, Response};
+use httptest::matchers::{all_of, matches, request};
+use httptest::responders::{cycle, Responder};
+use httptest::{responders::status_code, Expectation, Server};
+use std::future::Future;
+use std::pin::Pin;
 
 pub struct ReleaseServer {
     server: Server,
@@ -10,17 +15,31 @@ pub struct ReleaseServer {
 impl ReleaseServer {
     pub fn new(home_dir: &TempHomeDir) -> Self {
         let server = Server::run();
-        let download_url_template =
-            server.url_str("/any/arbitrary/path/{{version}}/{{basename}}.{{archive-format}}");
+        // like most releases, only published as .tar.gz, unless a test says otherwise
+        server.expect(
+            Expectation::matching(all_of![
+                request::method("GET"),
+                request::path(matches(r"\.tar\.(zst|xz)\.sha256$")),
+            ])
+            .times(..)
+            .respond_with(status_code(404)),
+        );
+        let release_server = Self { server };
         home_dir
             .settings()
-            .write_download_url_template(&download_url_template);
-        let manifest_url = server.url_str("/manifest.json");
-        home_dir.settings().write_manifest_url(&manifest_url);
+            .write_download_url_template(&release_server.download_url_template());
         home_dir
             .settings()
-            .write_dfxvm_latest_download_root_url(&server.url_str("/dfxvm-latest-download-root"));
-        Self { server }
+            .write_manifest_url(&release_server.manifest_url());
+        home_dir.settings().write_dfxvm_latest_download_root_url(
+            &release_server.server.url_str("/dfxvm-latest-download-root"),
+        );
+        release_server
+    }
+
+    pub fn download_url_template(&self) -> String {
+        self.server
+            .url_str("/any/arbitrary/path/{{version}}/{{basename}}.{{archive-format}}")
     }
 
     pub fn expect_get(&self, asset: &ReleaseAsset) {
@@ -30,6 +49,37 @@ impl ReleaseServer {
         );
     }
 
+    pub fn expect_get_times(&self, asset: &ReleaseAsset, times: usize) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", asset.url_path.clone()))
+                .times(times)
+                .respond_with(asset.ok_response()),
+        );
+    }
+
+    pub fn expect_get_with(&self, asset: &ReleaseAsset, times: usize, responder: RangeResponder) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", asset.url_path.clone()))
+                .times(times)
+                .respond_with(responder),
+        );
+    }
+
+    pub fn expect_head(&self, asset: &ReleaseAsset) {
+        self.server.expect(
+            Expectation::matching(request::method_path("HEAD", asset.url_path.clone()))
+                .respond_with(RangeResponder::new(asset)),
+        );
+    }
+
+    pub fn expect_no_get(&self, asset: &ReleaseAsset) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", asset.url_path.clone()))
+                .times(0)
+                .respond_with(status_code(404)),
+        );
+    }
+
     pub fn expect_get_respond_not_found(&self, asset: &ReleaseAsset) {
         self.server.expect(
             Expectation::matching(request::method_path("GET", asset.url_path.clone()))
@@ -37,6 +87,43 @@ impl ReleaseServer {
         );
     }
 
+    pub fn expect_get_respond_server_error(&self, asset: &ReleaseAsset) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", asset.url_path.clone()))
+                .respond_with(status_code(500)),
+        );
+    }
+
+    pub fn expect_get_manifest_respond_server_error(&self) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", "/manifest.json"))
+                .respond_with(status_code(500)),
+        );
+    }
+
+    // Expects one request to the path for each response, in order
+    pub fn expect_get_responses(&self, url_path: &str, responses: Vec<Box<dyn Responder>>) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", url_path.to_string()))
+                .times(responses.len())
+                .respond_with(cycle(responses)),
+        );
+    }
+
+    pub fn url(&self, asset: &ReleaseAsset) -> String {
+        self.server.url_str(&asset.url_path)
+    }
+
+    // The server can stand in for an http proxy, because its
+    // expectations match request paths, not hosts.
+    pub fn proxy_url(&self) -> String {
+        self.server.url_str("/")
+    }
+
+    pub fn manifest_url(&self) -> String {
+        self.server.url_str("/manifest.json")
+    }
+
     pub fn expect_get_manifest(&self, contents: &str) {
         self.server.expect(
             Expectation::matching(request::method_path("GET", "/manifest.json")).respond_with(
@@ -48,8 +135,25 @@ impl ReleaseServer {
         );
     }
 
+    pub fn expect_get_manifest_signature(&self, contents: &str, key: &TestKey) {
+        self.server.expect(
+            Expectation::matching(request::method_path("GET", "/manifest.json.minisig"))
+                .respond_with(
+                    response::Builder::new()
+                        .status(200)
+                        .body(key.sign(contents.as_bytes()).into_bytes())
+                        .unwrap(),
+                ),
+        );
+    }
+
     pub fn expect_install_latest(&self) {
-        let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+        // a /bin/sh script, so that the install's smoke test can run it
+        // even when a test's PATH has no bash
+        let tarball = ReleaseAsset::dfx_tarball_with_dfx_contents(
+            "0.15.0",
+            b"#!/bin/sh\necho 'this is dfx 0.15.0'\n",
+        );
         let sha256 = ReleaseAsset::sha256(&tarball);
         self.expect_get(&tarball);
         self.expect_get(&sha256);
@@ -71,3 +175,57 @@ impl ReleaseServer {
         );
     }
 }
+
+// Serves an asset with support for range requests.
+pub struct RangeResponder {
+    contents: Vec<u8>,
+}
+
+impl RangeResponder {
+    pub fn new(asset: &ReleaseAsset) -> Self {
+        Self {
+            contents: asset.contents.clone(),
+        }
+    }
+}
+
+impl Responder for RangeResponder {
+    fn respond<'a>(
+        &mut self,
+        req: &'a Request<Bytes>,
+    ) -> Pin<Box<dyn Future<Output = Response<Bytes>> + Send + 'a>> {
+        let len = self.contents.len();
+        let builder = Response::builder().header("accept-ranges", "bytes");
+        let range = req
+            .headers()
+            .get("range")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_range(value, len));
+        let response = match range {
+            _ if req.method() == "HEAD" => builder
+                .status(200)
+                .header("content-length", len)
+                .body(Bytes::new()),
+            Some((start, end)) => builder
+                .status(206)
+                .header("content-range", format!("bytes {start}-{end}/{len}"))
+                .body(Bytes::copy_from_slice(&self.contents[start..=end])),
+            None => builder
+                .status(200)
+                .body(Bytes::copy_from_slice(&self.contents)),
+        };
+        let response = response.unwrap();
+        Box::pin(async move { response })
+    }
+}
+
+// "bytes=<start>-[<end>]", as inclusive offsets
+pub fn parse_range(value: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = value.strip_prefix("bytes=")?.split_once('-')?;
+    let start = start.parse().ok()?;
+    let end = match end {
+        "" => len - 1,
+        end => end.parse().ok()?,
+    };
+    Some((start, end))
+}
@@ -1,7 +1,8 @@
 use itertools::Itertools;
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::fs::create_dir_all;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Settings {
     path: PathBuf,
@@ -26,28 +27,104 @@ impl Settings {
             .collect()
     }
 
+    pub fn write_auto_install(&self, mode: &str) {
+        self.set_field("auto_install", mode);
+    }
+
     pub fn write_default_version(&self, version: &str) {
         self.set_field("default_version", version);
     }
 
+    pub fn write_download_cache_limit_mb(&self, limit: u64) {
+        self.set_field("download_cache_limit_mb", limit);
+    }
+
+    pub fn write_archive_formats(&self, formats: &[&str]) {
+        self.set_field("archive_formats", formats);
+    }
+
+    pub fn write_download_connections(&self, connections: u32) {
+        self.set_field("download_connections", connections);
+    }
+
     pub fn write_download_url_template(&self, url_template: &str) {
         self.set_field("download_url_template", url_template);
     }
 
+    pub fn write_download_url_templates(&self, url_templates: &[String]) {
+        self.set_field("download_url_template", url_templates);
+    }
+
     pub fn write_manifest_url(&self, url_template: &str) {
         self.set_field("manifest_url", url_template);
     }
 
+    pub fn write_manifest_urls(&self, urls: &[String]) {
+        self.set_field("manifest_url", urls);
+    }
+
     pub fn write_dfxvm_latest_download_root_url(&self, url_template: &str) {
         self.set_field("dfxvm_latest_download_root", url_template);
     }
 
+    pub fn write_ca_certificates(&self, path: &Path) {
+        self.set_field("ca_certificates", path);
+    }
+
+    pub fn write_client_certificate(&self, path: &Path) {
+        self.set_field("client_certificate", path);
+    }
+
+    pub fn write_no_proxy(&self, no_proxy: &str) {
+        self.set_field("no_proxy", no_proxy);
+    }
+
+    pub fn write_proxy(&self, url: &str) {
+        self.set_field("proxy", url);
+    }
+
+    pub fn write_read_timeout_secs(&self, secs: u64) {
+        self.set_field("read_timeout_secs", secs);
+    }
+
+    pub fn write_retries(&self, retries: u32) {
+        self.set_field("retries", retries);
+    }
+
+    pub fn write_retry_base_delay_ms(&self, delay: u64) {
+        self.set_field("retry_base_delay_ms", delay);
+    }
+
+    pub fn write_retry_deadline_secs(&self, secs: u64) {
+        self.set_field("retry_deadline_secs", secs);
+    }
+
+    pub fn write_revoked_keys(&self, keys: &[String]) {
+        self.set_field("revoked_keys", keys);
+    }
+
+    pub fn write_target(&self, target: &str) {
+        self.set_field("target", target);
+    }
+
+    pub fn write_trusted_keys(&self, keys: &[String]) {
+        self.set_field("trusted_keys", keys);
+    }
+
+    pub fn write_verify_signatures(&self, verify: bool) {
+        self.set_field("verify_signatures", verify);
+    }
+
+    pub fn write_warm_cache(&self, warm_cache: bool) {
+        self.set_field("warm_cache", warm_cache);
+    }
+
     pub fn write(&self, s: &str) {
         create_dir_all(self.path.parent().unwrap()).unwrap();
         std::fs::write(&self.path, s).unwrap();
     }
 
-    fn set_field(&self, name: &str, value: &str) {
+    fn set_field<T: Serialize>(&self, name: &str, value: T) {
         let json = self.read();
         let mut json = json.as_object().unwrap().clone();
         json.insert(name.to_string(), json!(value));
@@ -0,0 +1,47 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signer, SigningKey};
+
+// A minisign key pair, derived from a seed so that tests are repeatable
+pub struct TestKey {
+    key_id: [u8; 8],
+    signing_key: SigningKey,
+}
+
+impl TestKey {
+    pub fn new(seed: u8) -> Self {
+        Self {
+            key_id: [seed; 8],
+            signing_key: SigningKey::from_bytes(&[seed; 32]),
+        }
+    }
+
+    // as in the second line of a minisign.pub file
+    pub fn public_key(&self) -> String {
+        let mut bin = b"Ed".to_vec();
+        bin.extend(self.key_id);
+        bin.extend(self.signing_key.verifying_key().as_bytes());
+        STANDARD.encode(bin)
+    }
+
+    // the contents of a prehashed minisign signature file
+    pub fn sign(&self, contents: &[u8]) -> String {
+        let hash = Blake2b512::digest(contents);
+        let signature = self.signing_key.sign(&hash).to_bytes();
+
+        let mut bin = b"ED".to_vec();
+        bin.extend(self.key_id);
+        bin.extend(signature);
+
+        let trusted_comment = "timestamp:1700000000\tfile:test";
+        let mut global = signature.to_vec();
+        global.extend(trusted_comment.as_bytes());
+        let global_signature = self.signing_key.sign(&global).to_bytes();
+
+        format!(
+            "untrusted comment: signature from test key\n{}\ntrusted comment: {trusted_comment}\n{}\n",
+            STANDARD.encode(bin),
+            STANDARD.encode(global_signature)
+        )
+    }
+}
@@ -138,6 +138,18 @@ impl TempHomeDir {
         project_dirs::data_local_dir(self.path(), self.xdg_data_home.as_deref())
     }
 
+    pub fn download_cache_dir(&self) -> PathBuf {
+        self.data_local_dir().join("cache").join("downloads")
+    }
+
+    pub fn locks_dir(&self) -> PathBuf {
+        self.data_local_dir().join("locks")
+    }
+
+    pub fn tags_cache_path(&self) -> PathBuf {
+        self.data_local_dir().join("cache").join("tags.json")
+    }
+
     pub fn versions_dir(&self) -> PathBuf {
         self.data_local_dir().join("versions")
     }
@@ -184,6 +196,10 @@ impl TempHomeDir {
         self.installed_bin_dir().join("dfx")
     }
 
+    pub fn installed_shim_path(&self, name: &str) -> PathBuf {
+        self.installed_bin_dir().join(name)
+    }
+
     pub fn installed_env_path(&self) -> PathBuf {
         self.data_local_dir().join("env")
     }
@@ -197,6 +213,16 @@ impl TempHomeDir {
         bin_path
     }
 
+    // a dfx binary outside of the versions directory, such as a local build
+    pub fn create_local_dfx_build(&self, dirname: &str, snippet: &str) -> PathBuf {
+        let dir = self.path().join(dirname);
+        create_dir_all(&dir).unwrap();
+        let bin_path = dir.join("dfx");
+        let script = file_contents::bash_script(snippet);
+        create_executable(&bin_path, &script);
+        bin_path
+    }
+
     fn next_script_path(&self) -> PathBuf {
         let counter = self.script_counter.get();
         self.script_counter.set(counter + 1);
@@ -1,6 +1,8 @@
+use crate::common::file_contents::{manifest_json, manifest_json_with_tags};
 use crate::common::paths::prepend_to_minimal_path;
-use crate::common::TempHomeDir;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
 use assert_cmd::prelude::*;
+use predicates::boolean::PredicateBooleanExt;
 use predicates::str::*;
 use std::os::unix::fs::PermissionsExt;
 
@@ -166,6 +168,406 @@ fn version_from_dfx_json_takes_precedence_over_settings() {
         .stdout("this is the zero point seven point four dfx executable\n");
 }
 
+#[test]
+fn version_req_from_dfx_json_uses_highest_matching_installed_version() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.23.0", "echo 'fail'");
+    home_dir.create_executable_dfx_script(
+        "0.24.1",
+        "echo 'this is the zero point twenty-four point one dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.24.0", "echo 'fail'");
+    home_dir.create_executable_dfx_script("0.25.0", "echo 'fail'");
+
+    for requirement in ["^0.24", ">=0.23.1, <0.25", "0.24.*"] {
+        let mut cmd = home_dir.dfx();
+
+        let tempdir = home_dir.new_project_temp_dir();
+        let dfx_json = tempdir.path().join("dfx.json");
+        std::fs::write(dfx_json, format!(r#"{{"dfx": "{requirement}"}}"#)).unwrap();
+        cmd.current_dir(&tempdir);
+
+        cmd.assert()
+            .success()
+            .stdout("this is the zero point twenty-four point one dfx executable\n");
+    }
+}
+
+#[test]
+fn exact_version_from_dfx_json_is_not_a_requirement() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.8",
+        "echo 'this is the zero point seven point eight dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.7.9", "echo 'fail'");
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let dfx_json = tempdir.path().join("dfx.json");
+    std::fs::write(dfx_json, r#"{"dfx": "0.7.8"}"#).unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point seven point eight dfx executable\n");
+}
+
+#[test]
+fn version_req_from_dfx_json_with_no_matching_installed_version() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.4.0", "echo 'fail'");
+    home_dir.create_executable_dfx_script("0.6.0", "echo 'fail'");
+
+    // manifest lists 0.5.0 and 0.5.2
+    server.expect_get_manifest(&manifest_json("0.5.2"));
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let dfx_json = tempdir.path().join("dfx.json");
+    std::fs::write(dfx_json, r#"{"dfx": "^0.5"}"#).unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr(is_match("no installed dfx version matches '\\^0.5' from .*/dfx.json").unwrap())
+        .stderr(contains(
+            "Matching versions available to install: 0.5.2, 0.5.0",
+        ))
+        .stderr(contains("dfxvm install 0.5.2"));
+}
+
+#[test]
+fn version_req_from_dfx_json_with_no_matching_released_version() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    server.expect_get_manifest(&manifest_json("0.5.2"));
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let dfx_json = tempdir.path().join("dfx.json");
+    std::fs::write(dfx_json, r#"{"dfx": ">=0.9, <0.10"}"#).unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .failure()
+        .stderr(contains(
+            "no installed dfx version matches '>=0.9, <0.10' from",
+        ))
+        .stderr(contains("No released dfx version matches it."));
+}
+
+#[test]
+fn version_from_dfx_version_file() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.8.1",
+        "echo 'this is the zero point eight point one dfx executable'",
+    );
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.8.1\n").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point eight point one dfx executable\n");
+}
+
+#[test]
+fn version_from_tool_versions_file() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.8.2",
+        "echo 'this is the zero point eight point two dfx executable'",
+    );
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(
+        tempdir.path().join(".tool-versions"),
+        "# pinned tools\nnodejs 20.11.0\ndfx 0.8.2 0.8.0 # comment\nrust 1.75.0\n",
+    )
+    .unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point eight point two dfx executable\n");
+}
+
+#[test]
+fn searches_for_toolchain_files_in_parent_directories() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.8.3",
+        "echo 'this is the zero point eight point three dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let x = tempdir.path().join("x");
+    let y = x.join("y");
+    let z = y.join("z");
+    std::fs::create_dir_all(&z).unwrap();
+
+    // a .tool-versions without a dfx line doesn't stop the search
+    std::fs::write(y.join(".tool-versions"), "nodejs 20.11.0\n").unwrap();
+    std::fs::write(x.join(".tool-versions"), "dfx 0.8.3\n").unwrap();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.9.9").unwrap();
+
+    let mut cmd = home_dir.dfx();
+    cmd.current_dir(&z);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point eight point three dfx executable\n");
+}
+
+#[test]
+fn dfx_version_file_takes_precedence_over_tool_versions_file() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.8.4",
+        "echo 'this is the zero point eight point four dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.8.4").unwrap();
+    std::fs::write(tempdir.path().join(".tool-versions"), "dfx 0.9.9").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point eight point four dfx executable\n");
+}
+
+#[test]
+fn version_from_dfx_json_takes_precedence_over_toolchain_files() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.5",
+        "echo 'this is the zero point seven point five dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{"dfx": "0.7.5"}"#).unwrap();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.9.9").unwrap();
+    std::fs::write(tempdir.path().join(".tool-versions"), "dfx 0.9.9").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point seven point five dfx executable\n");
+}
+
+#[test]
+fn toolchain_file_in_subdirectory_takes_precedence_over_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.7",
+        "echo 'this is the zero point seven point seven dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let subdir = tempdir.path().join("x").join("y");
+    std::fs::create_dir_all(&subdir).unwrap();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{"dfx": "0.9.9"}"#).unwrap();
+    std::fs::write(tempdir.path().join("x").join(".dfx-version"), "0.7.7").unwrap();
+
+    let mut cmd = home_dir.dfx();
+    cmd.current_dir(&subdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point seven point seven dfx executable\n");
+}
+
+#[test]
+fn dfx_json_in_subdirectory_takes_precedence_over_toolchain_file() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.8",
+        "echo 'this is the zero point seven point eight dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let project = tempdir.path().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(tempdir.path().join(".tool-versions"), "dfx 0.9.9\n").unwrap();
+    std::fs::write(project.join("dfx.json"), r#"{"dfx": "0.7.8"}"#).unwrap();
+
+    let mut cmd = home_dir.dfx();
+    cmd.current_dir(&project);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point seven point eight dfx executable\n");
+}
+
+#[test]
+fn version_from_toolchain_file_used_when_dfx_json_has_no_version() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.6",
+        "echo 'this is the zero point seven point six dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+    home_dir.settings().write_default_version("0.9.9");
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{}"#).unwrap();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.7.6").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point seven point six dfx executable\n");
+}
+
+#[test]
+fn version_req_from_dfx_version_file() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.8.0", "echo 'fail'");
+    home_dir.create_executable_dfx_script(
+        "0.8.5",
+        "echo 'this is the zero point eight point five dfx executable'",
+    );
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".dfx-version"), "~0.8").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point eight point five dfx executable\n");
+}
+
+#[test]
+fn malformed_version_in_toolchain_files() {
+    let home_dir = TempHomeDir::new();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".dfx-version"), "3.y").unwrap();
+    home_dir
+        .dfx()
+        .current_dir(&tempdir)
+        .assert()
+        .failure()
+        .stderr(is_match("failed to parse version '3.y' from .*/.dfx-version").unwrap())
+        .stderr(is_match("caused by: .*minor version number").unwrap());
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".tool-versions"), "dfx 3.y").unwrap();
+    home_dir
+        .dfx()
+        .current_dir(&tempdir)
+        .assert()
+        .failure()
+        .stderr(is_match("failed to parse version '3.y' from .*/.tool-versions").unwrap())
+        .stderr(is_match("caused by: .*minor version number").unwrap());
+}
+
+#[test]
+fn version_from_settings_when_toolchain_files_are_empty() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.2.4",
+        "echo 'this is the zero point two point four dfx executable'",
+    );
+    home_dir.settings().write_default_version("0.2.4");
+
+    let mut cmd = home_dir.dfx();
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join(".dfx-version"), "\n").unwrap();
+    std::fs::write(tempdir.path().join(".tool-versions"), "dfx\n").unwrap();
+    cmd.current_dir(&tempdir);
+
+    cmd.assert()
+        .success()
+        .stdout("this is the zero point two point four dfx executable\n");
+}
+
+#[test]
+fn version_from_override_takes_precedence_over_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.7.3",
+        "echo 'this is the zero point seven point three dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{"dfx": "0.9.9"}"#).unwrap();
+    std::fs::write(tempdir.path().join(".dfx-version"), "0.9.9").unwrap();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&tempdir)
+        .arg("override")
+        .arg("set")
+        .arg("0.7.3")
+        .assert()
+        .success();
+
+    home_dir
+        .dfx()
+        .current_dir(&tempdir)
+        .assert()
+        .success()
+        .stdout("this is the zero point seven point three dfx executable\n");
+}
+
+#[test]
+fn version_from_environment_takes_precedence_over_override() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script(
+        "0.6.8",
+        "echo 'this is the zero point six point eight dfx executable'",
+    );
+    home_dir.create_executable_dfx_script("0.9.9", "echo 'fail'");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    home_dir
+        .dfxvm()
+        .current_dir(&tempdir)
+        .arg("override")
+        .arg("set")
+        .arg("0.9.9")
+        .assert()
+        .success();
+
+    home_dir
+        .dfx()
+        .current_dir(&tempdir)
+        .env("DFX_VERSION", "0.6.8")
+        .assert()
+        .success()
+        .stdout("this is the zero point six point eight dfx executable\n");
+}
+
 #[test]
 fn dfx_json_with_no_version() {
     let home_dir = TempHomeDir::new();
@@ -365,7 +767,8 @@ fn malformed_version_in_dfx_json() {
 
     let tempdir = home_dir.new_project_temp_dir();
     let dfx_json = tempdir.path().join("dfx.json");
-    std::fs::write(dfx_json, r#"{"dfx": "3.x"}"#).unwrap();
+    // "3.x" is a valid version requirement, but "3.y" is neither a version nor a requirement
+    std::fs::write(dfx_json, r#"{"dfx": "3.y"}"#).unwrap();
     cmd.current_dir(&tempdir);
 
     cmd.assert()
@@ -499,3 +902,227 @@ fn env_vars_provided() {
         .stdout(contains("DFX_VERSION: 0.7.9\n"))
         .stdout(contains(format!("PATH: {}\n", expected_path)));
 }
+
+#[test]
+fn version_not_installed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.8.1");
+
+    home_dir
+        .dfx()
+        .arg("--version")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("dfx 0.8.1 is not installed."))
+        .stderr(contains("dfxvm install 0.8.1"));
+    assert!(!home_dir.installed_dfx_path("0.8.1").exists());
+}
+
+#[test]
+fn auto_install_always_from_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_default_version("0.8.1");
+    home_dir.settings().write_auto_install("always");
+
+    let tarball = ReleaseAsset::dfx_tarball("0.8.1", "echo 'this is dfx 0.8.1'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfx()
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.8.1\n")
+        .stderr(contains("installing dfx 0.8.1"));
+    assert!(home_dir.installed_dfx_path("0.8.1").exists());
+}
+
+#[test]
+fn auto_install_always_from_environment() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.8.1", "echo 'this is dfx 0.8.1'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfx()
+        .arg("+0.8.1")
+        .arg("--version")
+        .env("DFXVM_AUTO_INSTALL", "always")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.8.1\n");
+}
+
+#[test]
+fn auto_install_environment_overrides_settings() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.8.1");
+    home_dir.settings().write_auto_install("always");
+
+    home_dir
+        .dfx()
+        .env("DFXVM_AUTO_INSTALL", "never")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("dfx 0.8.1 is not installed."));
+    assert!(!home_dir.installed_dfx_path("0.8.1").exists());
+}
+
+#[test]
+fn auto_install_prompt_without_terminal_does_not_install() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.8.1");
+    home_dir.settings().write_auto_install("prompt");
+
+    home_dir
+        .dfx()
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("dfx 0.8.1 is not installed."))
+        .stderr(contains("Install it now?").not());
+    assert!(!home_dir.installed_dfx_path("0.8.1").exists());
+}
+
+#[test]
+fn auto_install_invalid_environment_value() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.8.1");
+
+    home_dir
+        .dfx()
+        .env("DFXVM_AUTO_INSTALL", "sometimes")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains(
+            "invalid DFXVM_AUTO_INSTALL value 'sometimes': expected never, prompt, or always",
+        ));
+}
+
+#[test]
+fn tag_from_commandline() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.8.1", "echo 'this is dfx 0.8.1'");
+    home_dir.settings().write_default_version("0.5.0");
+    server.expect_get_manifest(&manifest_json_with_tags(&[
+        ("latest", "0.8.1"),
+        ("beta", "0.9.0-beta.1"),
+    ]));
+
+    home_dir
+        .dfx()
+        .arg("+latest")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.8.1\n");
+}
+
+#[test]
+fn tag_from_environment() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.9.0-beta.1", "echo \"dfx $DFX_VERSION\"");
+    server.expect_get_manifest(&manifest_json_with_tags(&[
+        ("latest", "0.8.1"),
+        ("beta", "0.9.0-beta.1"),
+    ]));
+
+    home_dir
+        .dfx()
+        .env("DFX_VERSION", "beta")
+        .assert()
+        .success()
+        .stdout("dfx 0.9.0-beta.1\n");
+}
+
+#[test]
+fn tag_from_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.8.1", "echo 'this is dfx 0.8.1'");
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.8.1")]));
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{"dfx": "latest"}"#).unwrap();
+
+    home_dir
+        .dfx()
+        .current_dir(&tempdir)
+        .assert()
+        .success()
+        .stdout("this is dfx 0.8.1\n");
+}
+
+#[test]
+fn tags_are_cached() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.8.1", "echo 'this is dfx 0.8.1'");
+    // the manifest is fetched only once
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.8.1")]));
+
+    for _ in 0..3 {
+        home_dir
+            .dfx()
+            .arg("+latest")
+            .assert()
+            .success()
+            .stdout("this is dfx 0.8.1\n");
+    }
+    assert!(home_dir.tags_cache_path().exists());
+}
+
+#[test]
+fn stale_tags_cache_is_refreshed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.8.1", "echo 'this is dfx 0.8.1'");
+    home_dir.create_executable_dfx_script("0.8.2", "echo 'this is dfx 0.8.2'");
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.8.2")]));
+
+    let cache = format!(
+        r#"{{"manifest_url": "{}", "fetched_at": 0, "tags": {{"latest": "0.8.1"}}}}"#,
+        server.manifest_url()
+    );
+    std::fs::create_dir_all(home_dir.tags_cache_path().parent().unwrap()).unwrap();
+    std::fs::write(home_dir.tags_cache_path(), cache).unwrap();
+
+    home_dir
+        .dfx()
+        .arg("+latest")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.8.2\n");
+}
+
+#[test]
+fn unknown_tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[
+        ("latest", "0.8.1"),
+        ("beta", "0.9.0-beta.1"),
+    ]));
+
+    home_dir
+        .dfx()
+        .arg("+nightly")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains(
+            "unknown dfx version tag 'nightly'. Available tags: beta, latest",
+        ));
+}
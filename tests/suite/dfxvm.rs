@@ -1,8 +1,23 @@
+mod archive_format;
+mod cache;
 mod default;
+mod http;
 mod install;
+mod journal;
+mod link;
 mod list;
+mod lock;
+mod mirror;
+mod overrides;
+mod retry;
+mod run;
 mod self_uninstall;
 mod self_update;
+mod shim;
+mod show;
+mod signature;
+mod target;
 mod uninstall;
 mod update;
 mod version;
+mod which;
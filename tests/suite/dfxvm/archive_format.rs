@@ -0,0 +1,203 @@
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::str::*;
+use serde_json::json;
+use std::process::Command;
+
+fn assert_installed(home_dir: &TempHomeDir) {
+    let dfx_path = home_dir.installed_dfx_path("0.15.0");
+    Command::new(dfx_path)
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn prefers_zstd_archive() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let zst = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.zst");
+    let gz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.gz");
+    server.expect_get(&zst);
+    server.expect_get(&ReleaseAsset::sha256(&zst));
+    server.expect_no_get(&ReleaseAsset::sha256(&gz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(is_match("downloaded .*/dfx-.*.tar.zst\n").unwrap());
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn falls_back_to_xz_archive() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let xz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.xz");
+    server.expect_get(&xz);
+    server.expect_get(&ReleaseAsset::sha256(&xz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(is_match("downloaded .*/dfx-.*.tar.xz\n").unwrap());
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn no_archive_in_any_format() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let gz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.gz");
+    server.expect_get_respond_not_found(&ReleaseAsset::sha256(&gz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("no such version"));
+}
+
+#[test]
+fn archive_formats_setting_restricts_formats() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_archive_formats(&["tar.gz"]);
+
+    let zst = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.zst");
+    let gz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.gz");
+    server.expect_no_get(&ReleaseAsset::sha256(&zst));
+    server.expect_get(&gz);
+    server.expect_get(&ReleaseAsset::sha256(&gz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn archive_formats_setting_orders_formats() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_archive_formats(&["tar.xz", "tar.zst"]);
+
+    let zst = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.zst");
+    let xz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.xz");
+    server.expect_no_get(&ReleaseAsset::sha256(&zst));
+    server.expect_get(&xz);
+    server.expect_get(&ReleaseAsset::sha256(&xz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn manifest_declares_archive_formats() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let manifest = json!({
+        "tags": { "latest": "0.15.0" },
+        "versions": ["0.15.0"],
+        "archive_formats": { "0.15.0": ["tar.xz", "tar.gz"] }
+    })
+    .to_string();
+    server.expect_get_manifest(&manifest);
+    let zst = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.zst");
+    let xz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.xz");
+    server.expect_no_get(&ReleaseAsset::sha256(&zst));
+    server.expect_get(&xz);
+    server.expect_get(&ReleaseAsset::sha256(&xz));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("latest")
+        .assert()
+        .success()
+        .stderr(is_match("downloaded .*/dfx-.*.tar.xz\n").unwrap());
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn url_template_without_archive_format() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let zst = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.zst");
+    let template = server.url(&zst).replace("0.15.0", "{{version}}");
+    home_dir.settings().write_download_url_template(&template);
+
+    server.expect_get(&zst);
+    server.expect_get(&ReleaseAsset::sha256(&zst));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn install_from_xz_file() {
+    let home_dir = TempHomeDir::new();
+    let xz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.xz");
+    let sha256 = ReleaseAsset::sha256(&xz);
+    let path = home_dir.join(&xz.filename);
+    std::fs::write(&path, &xz.contents).unwrap();
+    std::fs::write(home_dir.join(&sha256.filename), &sha256.contents).unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&path)
+        .assert()
+        .success();
+    assert_installed(&home_dir);
+}
+
+#[test]
+fn install_from_file_of_unknown_format() {
+    let home_dir = TempHomeDir::new();
+    let gz = ReleaseAsset::dfx_archive("0.15.0", "echo 'this is dfx 0.15.0'", "tar.gz");
+    let path = home_dir.join("dfx.tar.bz2");
+    std::fs::write(&path, &gz.contents).unwrap();
+    let sha256 = crate::common::file_contents::sha256("dfx.tar.bz2", &gz.contents);
+    std::fs::write(home_dir.join("dfx.tar.bz2.sha256"), sha256).unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(contains("unknown archive format"));
+}
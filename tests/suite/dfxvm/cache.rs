@@ -0,0 +1,232 @@
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::str::*;
+use std::path::PathBuf;
+
+fn cached_tarball_paths(home_dir: &TempHomeDir) -> Vec<PathBuf> {
+    let dir = home_dir.download_cache_dir();
+    if !dir.exists() {
+        return vec![];
+    }
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| !path.file_name().unwrap().to_str().unwrap().starts_with('.'))
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gz"))
+        .collect()
+}
+
+#[test]
+fn reinstall_uses_cached_download() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get_times(&tarball, 1);
+    server.expect_get_times(&sha256, 2);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("using cached download of"))
+        .stderr(contains("installed dfx 0.15.0"));
+
+    home_dir
+        .new_command(home_dir.installed_dfx_path("0.15.0"))
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn corrupt_cached_download_is_downloaded_again() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get_times(&tarball, 2);
+    server.expect_get_times(&sha256, 2);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    let cached = cached_tarball_paths(&home_dir);
+    assert_eq!(cached.len(), 1);
+    std::fs::write(&cached[0], "corrupt").unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("removing corrupt cached download"))
+        .stderr(contains("installed dfx 0.15.0"));
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+    assert_eq!(cached_tarball_paths(&home_dir).len(), 1);
+}
+
+#[test]
+fn list() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(contains(tarball.url_path.as_str()));
+}
+
+#[test]
+fn list_empty() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn clear() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    assert_eq!(cached_tarball_paths(&home_dir).len(), 1);
+
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("clear")
+        .assert()
+        .success()
+        .stderr(contains("cleared the download cache"));
+
+    assert!(cached_tarball_paths(&home_dir).is_empty());
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout("");
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn install_prunes_to_size_limit() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_download_cache_limit_mb(0);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("removed 1 cached download(s)"));
+
+    assert!(cached_tarball_paths(&home_dir).is_empty());
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn prune_keeps_downloads_within_limit() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("prune")
+        .assert()
+        .success()
+        .stderr(is_empty());
+    assert_eq!(cached_tarball_paths(&home_dir).len(), 1);
+
+    home_dir.settings().write_download_cache_limit_mb(0);
+    home_dir
+        .dfxvm()
+        .arg("cache")
+        .arg("prune")
+        .assert()
+        .success()
+        .stderr(contains("removed 1 cached download(s)"));
+    assert!(cached_tarball_paths(&home_dir).is_empty());
+}
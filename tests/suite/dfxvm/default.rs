@@ -1,4 +1,5 @@
-use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use crate::common::file_contents::{manifest_json_with_tags, manifest_json_with_versions};
+use crate::common::{HeldLock, ReleaseAsset, ReleaseServer, TempHomeDir};
 use assert_cmd::prelude::*;
 use predicates::str::contains;
 
@@ -261,3 +262,63 @@ fn default_leaves_unknown_settings() {
 
     home_dir.dfx().assert().success().stdout("hi from 0.6.3\n");
 }
+
+#[test]
+fn set_default_to_tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.6.3")]));
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .arg("default")
+        .arg("latest")
+        .assert()
+        .success()
+        .stderr(contains("set default version to dfx 0.6.3"));
+
+    // the tag is resolved when setting the default, not each time dfx runs
+    assert_eq!(home_dir.settings().read_default_version(), "0.6.3");
+}
+
+#[test]
+fn set_default_to_partial_version() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_versions(
+        "0.7.0",
+        &["0.6.1", "0.6.3", "0.7.0"],
+    ));
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .arg("default")
+        .arg("0.6")
+        .assert()
+        .success()
+        .stderr(contains(
+            "selected dfx 0.6.3, the newest release matching ^0.6",
+        ))
+        .stderr(contains("set default version to dfx 0.6.3"));
+
+    assert_eq!(home_dir.settings().read_default_version(), "0.6.3");
+}
+
+#[test]
+fn waits_for_settings_lock() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    let lock = HeldLock::acquire(&home_dir.locks_dir().join("settings.lock"), 5150);
+    let mut cmd = home_dir.dfxvm();
+    cmd.arg("default").arg("0.6.3");
+    lock.release_when_waited_for(cmd)
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock held by pid 5150"))
+        .stderr(contains("set default version to dfx 0.6.3"));
+
+    assert_eq!(home_dir.settings().read_default_version(), "0.6.3");
+}
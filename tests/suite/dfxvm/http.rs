@@ -0,0 +1,200 @@
+use crate::common::file_contents::manifest_json;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use httptest::matchers::request;
+use httptest::responders::{delay_and_then, status_code};
+use httptest::{Expectation, Server};
+use predicates::str::*;
+use std::time::Duration;
+
+// a host that doesn't resolve, so it can only be reached through a proxy
+const UNREACHABLE_DOWNLOAD_URL_TEMPLATE: &str =
+    "http://releases.invalid/any/arbitrary/path/{{version}}/{{basename}}.{{archive-format}}";
+
+// nothing listens on the discard port
+const DEAD_PROXY: &str = "http://127.0.0.1:9";
+
+fn expect_install(server: &ReleaseServer) {
+    let tarball = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+}
+
+#[test]
+fn proxy_from_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_download_url_template(UNREACHABLE_DOWNLOAD_URL_TEMPLATE);
+    home_dir.settings().write_proxy(&server.proxy_url());
+    expect_install(&server);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn proxy_from_environment() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_download_url_template(UNREACHABLE_DOWNLOAD_URL_TEMPLATE);
+    expect_install(&server);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .env("HTTP_PROXY", server.proxy_url())
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn proxy_from_settings_takes_precedence_over_environment() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_download_url_template(UNREACHABLE_DOWNLOAD_URL_TEMPLATE);
+    home_dir.settings().write_proxy(&server.proxy_url());
+    expect_install(&server);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .env("HTTP_PROXY", DEAD_PROXY)
+        .assert()
+        .success();
+}
+
+#[test]
+fn no_proxy_bypasses_proxy() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_proxy(DEAD_PROXY);
+    home_dir
+        .settings()
+        .write_no_proxy("example.com,127.0.0.1,::1");
+    expect_install(&server);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn invalid_proxy() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_proxy("http://[::1");
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid proxy url 'http://[::1'"));
+    assert!(!home_dir.dfx_version_dir("0.14.0").exists());
+}
+
+#[test]
+fn ca_certificates_file_missing() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+    let path = home_dir.join("corporate-ca.pem");
+    home_dir.settings().write_ca_certificates(&path);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains(format!("failed to read {}", path.display())));
+}
+
+#[test]
+fn ca_certificates_file_without_certificates() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+    let path = home_dir.join("corporate-ca.pem");
+    std::fs::write(&path, "not a certificate").unwrap();
+    home_dir.settings().write_ca_certificates(&path);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "no certificates found in {}",
+            path.display()
+        )));
+}
+
+#[test]
+fn invalid_client_certificate() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+    let path = home_dir.join("client.pem");
+    std::fs::write(&path, "not a certificate or key").unwrap();
+    home_dir.settings().write_client_certificate(&path);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "failed to parse the client certificate and key in {}",
+            path.display()
+        )));
+}
+
+#[test]
+fn manifest_fetch_uses_http_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_manifest_url("http://manifest.invalid/manifest.json");
+    home_dir.settings().write_proxy(&server.proxy_url());
+    server.expect_get_manifest(&manifest_json("0.14.0"));
+
+    home_dir
+        .dfxvm()
+        .args(["list", "--available"])
+        .assert()
+        .success()
+        .stdout(contains("0.5.2"));
+}
+
+#[test]
+fn read_timeout() {
+    let home_dir = TempHomeDir::new();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path("GET", "/manifest.json"))
+            .times(1..)
+            .respond_with(delay_and_then(Duration::from_secs(5), status_code(200))),
+    );
+    home_dir
+        .settings()
+        .write_manifest_url(&server.url_str("/manifest.json"));
+    home_dir.settings().write_read_timeout_secs(1);
+
+    home_dir
+        .dfxvm()
+        .args(["list", "--available"])
+        .assert()
+        .failure()
+        .stderr(contains("retry in"));
+}
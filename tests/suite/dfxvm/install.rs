@@ -1,5 +1,9 @@
-use crate::common::{project_dirs, ReleaseAsset, ReleaseServer, TempHomeDir};
+use crate::common::file_contents::{self, manifest_json_with_tags, manifest_json_with_versions};
+use crate::common::{
+    project_dirs, FlakyServer, HeldLock, RangeResponder, ReleaseAsset, ReleaseServer, TempHomeDir,
+};
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 use predicates::str::*;
 use std::path::PathBuf;
 use std::process::Command;
@@ -136,6 +140,809 @@ fn version_does_not_exist() {
     cmd.assert()
         .failure()
         .stderr(contains("no such version"))
-        .stderr(is_match("404 Not Found.*.tar.gz.sha256").unwrap());
+        .stderr(is_match("404 Not Found.*.tar.gz.sha256").unwrap())
+        .stderr(contains("failed to install dfx 0.13.6").count(1));
     assert!(!home_dir.dfx_version_dir(version).exists());
 }
+
+#[test]
+fn install_tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[
+        ("latest", "0.15.0"),
+        ("beta", "0.16.0-beta.0"),
+    ]));
+
+    let tarball = ReleaseAsset::dfx_tarball("0.16.0-beta.0", "echo 'this is dfx 0.16.0-beta.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("beta")
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.16.0-beta.0"));
+    assert!(home_dir.installed_dfx_path("0.16.0-beta.0").exists());
+}
+
+#[test]
+fn install_unknown_tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.15.0")]));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("nightly")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "unknown dfx version tag 'nightly'. Available tags: latest",
+        ));
+}
+
+#[test]
+fn install_partial_version() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_versions(
+        "0.16.0",
+        &["0.14.9", "0.15.0", "0.15.2", "0.15.10", "0.16.0"],
+    ));
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.10", "echo 'this is dfx 0.15.10'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15")
+        .assert()
+        .success()
+        .stderr(contains(
+            "selected dfx 0.15.10, the newest release matching ^0.15",
+        ))
+        .stderr(contains("installed dfx 0.15.10"));
+    assert!(home_dir.installed_dfx_path("0.15.10").exists());
+}
+
+#[test]
+fn install_requirement_skips_prereleases() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_versions(
+        "0.15.1",
+        &["0.15.0", "0.15.1", "0.16.0-beta.0"],
+    ));
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.1", "echo 'this is dfx 0.15.1'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg(">=0.15")
+        .assert()
+        .success()
+        .stderr(contains("selected dfx 0.15.1"));
+}
+
+#[test]
+fn install_partial_version_no_match() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_versions(
+        "0.15.1",
+        &["0.15.0", "0.15.1", "0.17.0-beta.0"],
+    ));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.17")
+        .assert()
+        .failure()
+        .stderr(contains("no released dfx version matches '^0.17'"));
+}
+
+#[test]
+fn install_several_versions() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    for version in ["0.22.0", "0.23.0", "0.24.3"] {
+        let tarball = ReleaseAsset::dfx_tarball(version, &format!("echo 'this is dfx {version}'"));
+        let sha256 = ReleaseAsset::sha256(&tarball);
+        server.expect_get(&tarball);
+        server.expect_get(&sha256);
+    }
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.22.0", "0.23.0", "0.24.3", "0.23.0"])
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.22.0"))
+        .stderr(contains("installed dfx 0.23.0"))
+        .stderr(contains("installed dfx 0.24.3"));
+
+    for version in ["0.22.0", "0.23.0", "0.24.3"] {
+        let mut dfx_cmd = Command::new(home_dir.installed_dfx_path(version));
+        dfx_cmd
+            .assert()
+            .success()
+            .stdout(format!("this is dfx {version}\n"));
+    }
+}
+
+#[test]
+fn install_several_versions_one_fails() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.22.0", "echo 'this is dfx 0.22.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    let missing = ReleaseAsset::dfx_tarball("0.13.6", "echo 'this is dfx 0.13.6'");
+    server.expect_get_respond_not_found(&ReleaseAsset::sha256(&missing));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.13.6", "0.22.0"])
+        .assert()
+        .failure()
+        .stderr(contains("installed dfx 0.22.0"))
+        .stderr(contains("no such version"))
+        .stderr(contains("failed to install dfx 0.13.6").count(1))
+        .stderr(contains("error: failed to install 1 of 2 dfx versions\n"));
+
+    assert!(home_dir.installed_dfx_path("0.22.0").exists());
+    assert!(!home_dir.dfx_version_dir("0.13.6").exists());
+}
+
+#[test]
+fn install_requires_a_version() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .assert()
+        .failure()
+        .stderr(contains("required arguments were not provided"));
+}
+
+fn write_local_tarball(home_dir: &TempHomeDir, tarball: &ReleaseAsset) -> PathBuf {
+    let path = home_dir.join(&tarball.filename);
+    std::fs::write(&path, &tarball.contents).unwrap();
+    path
+}
+
+#[test]
+fn install_from_file_with_sha256() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .arg("--sha256")
+        .arg(tarball.sha256_hex().to_uppercase())
+        .assert()
+        .success()
+        .stderr(contains("verified checksum"))
+        .stderr(contains("installed dfx 0.15.0"));
+
+    let mut dfx_cmd = Command::new(home_dir.installed_dfx_path("0.15.0"));
+    dfx_cmd.arg("--version");
+    dfx_cmd.assert().success().stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn install_from_file_with_sha256_file() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+    write_local_tarball(&home_dir, &sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.15.0"));
+
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn install_from_file_incorrect_sha256() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let wrong = ReleaseAsset::dfx_tarball("0.15.0", "echo 'not it'");
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .arg("--sha256")
+        .arg(wrong.sha256_hex())
+        .assert()
+        .failure()
+        .stderr(contains("checksum did not match"));
+
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn install_from_file_requires_a_checksum() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .assert()
+        .failure()
+        .stderr(contains("no checksum for"))
+        .stderr(contains("pass --sha256"));
+
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn install_from_file_requires_an_exact_version() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .assert()
+        .failure()
+        .stderr(contains("--from-file requires exactly one version"));
+}
+
+#[test]
+fn install_from_file_requires_a_single_version() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "0.16.0", "--from-file"])
+        .arg(&tarball_path)
+        .assert()
+        .code(2)
+        .stderr(contains("--from-file requires exactly one version"))
+        .stderr(contains("Usage: dfxvm install"));
+
+    assert!(!home_dir.versions_dir().exists());
+}
+
+#[test]
+fn resume_interrupted_download() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let half = tarball.contents.len() / 2;
+    let server = FlakyServer::start(&home_dir, &tarball, &sha256, half, true);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("retry in"))
+        .stderr(contains(format!(
+            "resuming download of {} at byte {half}",
+            server.url(&tarball)
+        )))
+        .stderr(contains("verified checksum"))
+        .stderr(contains("installed dfx 0.15.0"));
+
+    assert_eq!(
+        server.tarball_range_headers(),
+        vec![None, Some(format!("bytes={half}-"))]
+    );
+    let mut dfx_cmd = Command::new(home_dir.installed_dfx_path("0.15.0"));
+    dfx_cmd.arg("--version");
+    dfx_cmd.assert().success().stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn restart_download_if_server_ignores_range() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let half = tarball.contents.len() / 2;
+    let _server = FlakyServer::start(&home_dir, &tarball, &sha256, half, false);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("verified checksum"))
+        .stderr(contains("installed dfx 0.15.0"));
+
+    let mut dfx_cmd = Command::new(home_dir.installed_dfx_path("0.15.0"));
+    dfx_cmd.arg("--version");
+    dfx_cmd.assert().success().stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn download_in_parts() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_download_connections(4);
+
+    // incompressible, so that the tarball is big enough to split
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut executable = b"#!/usr/bin/env bash\necho 'this is dfx 0.15.0'\nexit 0\n".to_vec();
+    executable.extend((0..5 * 1024 * 1024).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    }));
+    let tarball = ReleaseAsset::dfx_tarball_with_dfx_contents("0.15.0", &executable);
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_head(&tarball);
+    server.expect_get_with(&tarball, 4, RangeResponder::new(&tarball));
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("in 4 parts"))
+        .stderr(contains("verified checksum"));
+
+    let installed = std::fs::read(home_dir.installed_dfx_path("0.15.0")).unwrap();
+    assert_eq!(installed, executable);
+}
+
+#[test]
+fn small_download_is_not_split() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_download_connections(4);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_head(&tarball);
+    server.expect_get_with(&tarball, 1, RangeResponder::new(&tarball));
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("in 4 parts").not());
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn waits_for_another_install_of_same_version() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    let lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.15.0.lock"), 4321);
+    let mut cmd = home_dir.dfxvm();
+    cmd.arg("install").arg("0.15.0");
+    lock.release_when_waited_for(cmd)
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock held by pid 4321"))
+        .stderr(contains("installed dfx 0.15.0"));
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn installs_other_versions_while_waiting() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    for version in ["0.14.0", "0.15.0"] {
+        let tarball = ReleaseAsset::dfx_tarball(version, &format!("echo 'this is dfx {version}'"));
+        server.expect_get(&ReleaseAsset::sha256(&tarball));
+        server.expect_get(&tarball);
+    }
+
+    // the lock is only released once the other version is installed
+    let lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.15.0.lock"), 4324);
+    let mut cmd = home_dir.dfxvm();
+    cmd.args(["install", "0.14.0", "0.15.0"]);
+    lock.release_when_logged(cmd, "installed dfx 0.14.0")
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock held by pid 4324"))
+        .stderr(contains("installed dfx 0.15.0"));
+    assert!(home_dir.installed_dfx_path("0.14.0").exists());
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn skips_version_installed_while_waiting() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+
+    let lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.15.0.lock"), 4322);
+    home_dir.create_executable_dfx_script("0.15.0", "echo 'this is dfx 0.15.0'");
+    let mut cmd = home_dir.dfxvm();
+    cmd.arg("install").arg("0.15.0");
+    lock.release_when_waited_for(cmd)
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock held by pid 4322"))
+        .stderr(contains("dfx 0.15.0 is already installed"));
+}
+
+#[test]
+fn installs_of_different_versions_do_not_wait() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    let _lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.14.6.lock"), 4323);
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock").not());
+}
+
+#[test]
+fn mislabelled_release_is_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'dfx 0.14.2'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "the archive's dfx binary reports version 0.14.2, but dfx 0.15.0 was requested",
+        ));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn release_whose_dfx_fails_is_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'cannot start' >&2\nexit 3");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "--version failed with exit status: 3: cannot start",
+        ));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn release_whose_dfx_writes_a_lot_is_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    // more than fits in a pipe's buffer, on either stream
+    let script = "head -c 1000000 /dev/zero | tr '\\0' x >&2\n\
+                  head -c 1000000 /dev/zero | tr '\\0' x\n\
+                  echo\n\
+                  echo 'dfx 0.15.0'";
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", script);
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("checked that dfx 0.15.0 runs"));
+}
+
+#[test]
+fn release_whose_dfx_reports_no_version_is_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'hello'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("--version did not report a version: hello"));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn release_with_non_executable_dfx_is_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let script = file_contents::bash_script("echo 'this is dfx 0.15.0'");
+    let tarball = ReleaseAsset::dfx_tarball_with_dfx_mode("0.15.0", script.as_bytes(), 0o644);
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(is_match("dfx-.*/dfx is not executable").unwrap());
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn release_for_another_architecture_is_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let (machine, other_arch) = if cfg!(target_arch = "aarch64") {
+        (62u16, "x86_64")
+    } else {
+        (183u16, "aarch64")
+    };
+    // a 64-bit little-endian ELF header, enough to identify the machine
+    let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+    elf.resize(18, 0);
+    elf.extend(machine.to_le_bytes());
+    elf.resize(64, 0);
+
+    let tarball = ReleaseAsset::dfx_tarball_with_dfx_contents("0.15.0", &elf);
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "the archive's dfx binary is built for {other_arch}-linux"
+        )));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn install_from_file_checks_reported_version() {
+    let home_dir = TempHomeDir::new();
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'dfx 0.15.1'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+    write_local_tarball(&home_dir, &sha256);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path)
+        .assert()
+        .failure()
+        .stderr(contains(
+            "reports version 0.15.1, but dfx 0.15.0 was requested",
+        ));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+// a dfx that populates its cache when asked, as `dfx cache install` does
+fn dfx_with_cache_install(exit_code: i32) -> ReleaseAsset {
+    ReleaseAsset::dfx_tarball(
+        "0.15.0",
+        &format!(
+            r#"if [ "$1" = "cache" ] && [ "$2" = "install" ]; then
+    mkdir -p "$HOME/.cache/dfinity/versions/0.15.0"
+    touch "$HOME/.cache/dfinity/versions/0.15.0/dfx"
+    echo "Installed dfx 0.15.0 to cache."
+    exit {exit_code}
+fi
+echo 'this is dfx 0.15.0'"#
+        ),
+    )
+}
+
+#[test]
+fn warm_cache() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let tarball = dfx_with_cache_install(0);
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--warm-cache"])
+        .assert()
+        .success()
+        .stderr(contains("warming the dfx cache for dfx 0.15.0"))
+        .stderr(contains("Installed dfx 0.15.0 to cache."))
+        .stderr(contains("warmed the dfx cache in"));
+    assert!(home_dir
+        .dfinity_cache_versions_dir()
+        .join("0.15.0/dfx")
+        .exists());
+}
+
+#[test]
+fn warm_cache_setting() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_warm_cache(true);
+    let tarball = dfx_with_cache_install(0);
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0"])
+        .assert()
+        .success()
+        .stderr(contains("warmed the dfx cache in"));
+    assert!(home_dir
+        .dfinity_cache_versions_dir()
+        .join("0.15.0")
+        .exists());
+}
+
+#[test]
+fn cache_is_not_warmed_by_default() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let tarball = dfx_with_cache_install(0);
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0"])
+        .assert()
+        .success()
+        .stderr(contains("warming").not());
+    assert!(!home_dir
+        .dfinity_cache_versions_dir()
+        .join("0.15.0")
+        .exists());
+}
+
+#[test]
+fn cache_is_not_warmed_if_already_installed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.15.0", "exit 1");
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--warm-cache"])
+        .assert()
+        .success()
+        .stderr(contains("dfx 0.15.0 is already installed"))
+        .stderr(contains("warming").not());
+}
+
+#[test]
+fn warm_cache_fails() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let tarball = dfx_with_cache_install(3);
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--warm-cache"])
+        .assert()
+        .failure()
+        .stderr(contains("failed to warm the dfx cache for dfx 0.15.0").count(1))
+        .stderr(contains("`dfx cache install` failed for dfx 0.15.0"));
+    // the version is still installed
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn warm_cache_from_file() {
+    let home_dir = TempHomeDir::new();
+    let tarball = dfx_with_cache_install(0);
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let tarball_path = write_local_tarball(&home_dir, &tarball);
+    std::fs::write(home_dir.join(&sha256.filename), &sha256.contents).unwrap();
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--warm-cache", "--from-file"])
+        .arg(&tarball_path)
+        .assert()
+        .success()
+        .stderr(contains("warmed the dfx cache in"));
+    assert!(home_dir
+        .dfinity_cache_versions_dir()
+        .join("0.15.0")
+        .exists());
+}
@@ -0,0 +1,320 @@
+use crate::common::file_contents::bash_script;
+use crate::common::{HeldLock, ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use predicates::str::*;
+use std::fs::{create_dir_all, write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+fn journal_dir(home_dir: &TempHomeDir) -> PathBuf {
+    home_dir.data_local_dir().join("journal")
+}
+
+fn write_journal_entry(home_dir: &TempHomeDir, toolchain: &str, entry: &str) {
+    let journal_dir = journal_dir(home_dir);
+    create_dir_all(&journal_dir).unwrap();
+    write(journal_dir.join(format!("{toolchain}.json")), entry).unwrap();
+}
+
+// an extracted release, as it is before being renamed into place
+fn create_staged_dfx(dir: &Path, version: &str) {
+    create_dir_all(dir).unwrap();
+    let dfx = dir.join("dfx");
+    write(&dfx, bash_script(&format!("echo 'dfx {version}'"))).unwrap();
+    std::fs::set_permissions(&dfx, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn journal_entries(home_dir: &TempHomeDir) -> Vec<String> {
+    match journal_dir(home_dir).read_dir() {
+        Ok(entries) => entries
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+#[test]
+fn install_interrupted_before_staging_is_rolled_back() {
+    let home_dir = TempHomeDir::new();
+    let staging_dir = home_dir.versions_dir().join(".install-0.14.0");
+    create_dir_all(&staging_dir).unwrap();
+    write(staging_dir.join("partial"), "half an archive").unwrap();
+    write_journal_entry(
+        &home_dir,
+        "0.14.0",
+        r#"{ "operation": "install", "toolchain": "0.14.0", "staged": null }"#,
+    );
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("rolled back interrupted install of dfx 0.14.0"));
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn install_interrupted_after_staging_is_completed() {
+    let home_dir = TempHomeDir::new();
+    let staged = home_dir
+        .versions_dir()
+        .join(".install-0.14.0")
+        .join("dfx-x86_64-unknown-linux-gnu");
+    create_staged_dfx(&staged, "0.14.0");
+    let entry = serde_json::json!({
+        "operation": "install",
+        "toolchain": "0.14.0",
+        "staged": staged,
+    });
+    write_journal_entry(&home_dir, "0.14.0", &entry.to_string());
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(contains("0.14.0"))
+        .stderr(contains("completed interrupted install of dfx 0.14.0"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(home_dir.installed_dfx_path("0.14.0").exists());
+    assert!(journal_entries(&home_dir).is_empty());
+
+    home_dir
+        .dfx()
+        .arg("+0.14.0")
+        .assert()
+        .success()
+        .stdout("dfx 0.14.0\n");
+}
+
+#[test]
+fn install_interrupted_after_rename_is_completed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    let staging_dir = home_dir.versions_dir().join(".install-0.14.0");
+    create_dir_all(&staging_dir).unwrap();
+    let entry = serde_json::json!({
+        "operation": "install",
+        "toolchain": "0.14.0",
+        "staged": staging_dir.join("dfx-x86_64-unknown-linux-gnu"),
+    });
+    write_journal_entry(&home_dir, "0.14.0", &entry.to_string());
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("completed interrupted install of dfx 0.14.0"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn uninstall_interrupted_before_rename_is_completed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.13.1", "echo 'dfx 0.13.1'");
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    write_journal_entry(
+        &home_dir,
+        "0.13.1",
+        r#"{ "operation": "uninstall", "toolchain": "0.13.1" }"#,
+    );
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("completed interrupted uninstall of dfx 0.13.1"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn uninstall_interrupted_after_rename_is_completed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    let uninstall_dir = home_dir.versions_dir().join(".uninstall-0.13.1");
+    create_dir_all(&uninstall_dir).unwrap();
+    write(uninstall_dir.join("dfx"), "part of dfx").unwrap();
+    write_journal_entry(
+        &home_dir,
+        "0.13.1",
+        r#"{ "operation": "uninstall", "toolchain": "0.13.1" }"#,
+    );
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("completed interrupted uninstall of dfx 0.13.1"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn dfx_proxy_recovers_interrupted_operations() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    let staging_dir = home_dir.versions_dir().join(".install-0.15.0");
+    create_dir_all(&staging_dir).unwrap();
+    write_journal_entry(
+        &home_dir,
+        "0.15.0",
+        r#"{ "operation": "install", "toolchain": "0.15.0", "staged": null }"#,
+    );
+
+    home_dir
+        .dfx()
+        .arg("+0.14.0")
+        .assert()
+        .success()
+        .stdout("dfx 0.14.0\n")
+        .stderr(contains("rolled back interrupted install of dfx 0.15.0"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn leftover_staging_dirs_are_removed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    let install_dir = home_dir.versions_dir().join(".install-0.15.0");
+    create_dir_all(&install_dir).unwrap();
+    let uninstall_dir = home_dir.versions_dir().join(".uninstall-0.13.1");
+    create_dir_all(&uninstall_dir).unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "removed leftover {}",
+            install_dir.display()
+        )))
+        .stderr(contains(format!(
+            "removed leftover {}",
+            uninstall_dir.display()
+        )));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+}
+
+#[test]
+fn legacy_install_tempdirs_are_removed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.14.0", "echo 'dfx 0.14.0'");
+    let tempdir = home_dir.versions_dir().join(".installAbC123");
+    create_dir_all(tempdir.join("dfx-x86_64-unknown-linux-gnu")).unwrap();
+    write(tempdir.join("dfx-0.15.0.tar.gz"), "half an archive").unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains(format!("removed leftover {}", tempdir.display())));
+
+    assert!(!tempdir.exists());
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+}
+
+#[test]
+fn legacy_install_tempdirs_are_left_alone_while_versions_are_locked() {
+    let home_dir = TempHomeDir::new();
+    let tempdir = home_dir.versions_dir().join(".installAbC123");
+    create_dir_all(&tempdir).unwrap();
+
+    let _lock = HeldLock::acquire(&home_dir.locks_dir().join("versions.lock"), 1234);
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("removed leftover").not());
+
+    assert!(tempdir.exists());
+}
+
+#[test]
+fn operation_in_progress_is_left_alone() {
+    let home_dir = TempHomeDir::new();
+    let staging_dir = home_dir.versions_dir().join(".install-0.14.0");
+    create_dir_all(&staging_dir).unwrap();
+    write_journal_entry(
+        &home_dir,
+        "0.14.0",
+        r#"{ "operation": "install", "toolchain": "0.14.0", "staged": null }"#,
+    );
+
+    let _lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.14.0.lock"), 1234);
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(contains("interrupted").not());
+
+    assert!(staging_dir.exists());
+    assert_eq!(journal_entries(&home_dir), vec!["0.14.0.json"]);
+}
+
+#[test]
+fn finished_operations_leave_nothing_to_recover() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.14.0")
+        .assert()
+        .success();
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+    assert!(journal_entries(&home_dir).is_empty());
+
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("0.14.0")
+        .assert()
+        .success();
+    assert!(home_dir.dfx_version_dirs().is_empty());
+    assert!(journal_entries(&home_dir).is_empty());
+}
+
+#[test]
+fn failed_install_leaves_nothing_to_recover() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.14.0", "exit 3");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.14.0")
+        .assert()
+        .failure();
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+    assert!(journal_entries(&home_dir).is_empty());
+}
@@ -0,0 +1,242 @@
+use crate::common::TempHomeDir;
+use assert_cmd::prelude::*;
+use predicates::str::*;
+
+#[test]
+fn link_binary() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success()
+        .stderr(contains("linked dev to "));
+
+    home_dir
+        .dfx()
+        .arg("+dev")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is a dev build\n");
+}
+
+#[test]
+fn link_directory() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("target/debug", "echo 'this is a dev build'");
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(bin_path.parent().unwrap())
+        .assert()
+        .success();
+
+    home_dir
+        .dfx()
+        .arg("+dev")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is a dev build\n");
+}
+
+#[test]
+fn relink_replaces_binary() {
+    let home_dir = TempHomeDir::new();
+    let first = home_dir.create_local_dfx_build("first", "echo 'first build'");
+    let second = home_dir.create_local_dfx_build("second", "echo 'second build'");
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&first)
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&second)
+        .assert()
+        .success();
+
+    home_dir
+        .dfx()
+        .arg("+dev")
+        .assert()
+        .success()
+        .stdout("second build\n");
+}
+
+#[test]
+fn default_to_linked_toolchain() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir.create_executable_dfx_script("0.14.1", "echo 'this is dfx 0.14.1'");
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("default")
+        .arg("dev")
+        .assert()
+        .success()
+        .stderr(contains("set default version to dfx dev"));
+
+    assert_eq!(home_dir.settings().read_default_version(), "dev");
+    home_dir
+        .dfx()
+        .assert()
+        .success()
+        .stdout("this is a dev build\n");
+    home_dir
+        .dfxvm()
+        .arg("default")
+        .assert()
+        .success()
+        .stdout("dev\n");
+}
+
+#[test]
+fn list_shows_linked_toolchains() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir.create_executable_dfx_script("0.14.1", "...");
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success();
+    home_dir.settings().write_default_version("dev");
+
+    let bin_path = bin_path.canonicalize().unwrap();
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(format!(
+            "0.14.1\ndev (linked to {}) (default)\n",
+            bin_path.display()
+        ));
+}
+
+#[test]
+fn uninstall_removes_link_but_not_binary() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("dev")
+        .assert()
+        .success()
+        .stderr(contains("uninstalled dfx dev"));
+
+    assert!(!home_dir.dfx_version_dir("dev").exists());
+    assert!(bin_path.exists());
+    home_dir.dfxvm().arg("list").assert().success().stdout("");
+}
+
+#[test]
+fn shim_runs_linked_toolchain() {
+    let home_dir = TempHomeDir::new();
+    home_dir.install_dfxvm_bin();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .args(["shim", "add", "dfx-dev", "dev"])
+        .assert()
+        .success();
+
+    home_dir
+        .new_command(home_dir.installed_shim_path("dfx-dev"))
+        .assert()
+        .success()
+        .stdout("this is a dev build\n");
+}
+
+#[test]
+fn invalid_name() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("0.15.0")
+        .arg(&bin_path)
+        .assert()
+        .failure()
+        .stderr(contains("invalid toolchain name '0.15.0'"));
+}
+
+#[test]
+fn no_dfx_binary() {
+    let home_dir = TempHomeDir::new();
+    let empty_dir = home_dir.join("empty");
+    std::fs::create_dir_all(&empty_dir).unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&empty_dir)
+        .assert()
+        .failure()
+        .stderr(contains("no dfx binary at"));
+    assert!(!home_dir.dfx_version_dir("dev").exists());
+}
+
+#[test]
+fn missing_binary_after_link() {
+    let home_dir = TempHomeDir::new();
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("dev")
+        .arg(&bin_path)
+        .assert()
+        .success();
+    std::fs::remove_file(&bin_path).unwrap();
+
+    home_dir
+        .dfx()
+        .arg("+dev")
+        .assert()
+        .failure()
+        .stderr(contains("dfx dev is not linked to an existing dfx binary"));
+}
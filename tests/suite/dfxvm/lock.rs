@@ -0,0 +1,316 @@
+use crate::common::file_contents::bash_script;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use predicates::str::*;
+use serde_json::{json, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn project_dir(home_dir: &TempHomeDir, dfx_json: &str) -> TempDir {
+    let project_dir = home_dir.new_project_temp_dir();
+    std::fs::write(project_dir.path().join("dfx.json"), dfx_json).unwrap();
+    project_dir
+}
+
+fn read_lockfile(project_dir: &Path) -> Value {
+    let contents = std::fs::read_to_string(project_dir.join("dfxvm.lock")).unwrap();
+    serde_json::from_str(&contents).unwrap()
+}
+
+fn write_lockfile(project_dir: &Path, lockfile: Value) {
+    std::fs::write(project_dir.join("dfxvm.lock"), lockfile.to_string()).unwrap();
+}
+
+#[test]
+fn locks_version_in_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_no_get(&tarball);
+
+    home_dir
+        .dfxvm()
+        .arg("lock")
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "locked dfx 0.14.0 for {}: {}",
+            ReleaseAsset::host_target("0.14.0"),
+            tarball.sha256_hex()
+        )));
+
+    assert_eq!(
+        read_lockfile(project_dir.path()),
+        json!({
+            "versions": {
+                "0.14.0": {
+                    ReleaseAsset::host_target("0.14.0"): {
+                        "url": server.url(&tarball),
+                        "sha256": tarball.sha256_hex(),
+                    }
+                }
+            }
+        })
+    );
+}
+
+#[test]
+fn locks_versions_for_targets() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, "{}");
+
+    let dfx = bash_script("echo 'dfx 0.15.0'").into_bytes();
+    let linux_x86_64 =
+        ReleaseAsset::dfx_tarball_for_target("0.15.0", "x86_64-unknown-linux-gnu", &dfx);
+    let linux_aarch64 =
+        ReleaseAsset::dfx_tarball_for_target("0.15.0", "aarch64-unknown-linux-gnu", b"other");
+    server.expect_get(&ReleaseAsset::sha256(&linux_x86_64));
+    server.expect_get(&ReleaseAsset::sha256(&linux_aarch64));
+
+    home_dir
+        .dfxvm()
+        .args(["lock", "0.15.0"])
+        .args(["--target", "x86_64-unknown-linux-gnu"])
+        .args(["--target", "aarch64-unknown-linux-gnu"])
+        .current_dir(&project_dir)
+        .assert()
+        .success();
+
+    let lockfile = read_lockfile(project_dir.path());
+    let targets = &lockfile["versions"]["0.15.0"];
+    assert_eq!(
+        targets["x86_64-unknown-linux-gnu"]["sha256"],
+        linux_x86_64.sha256_hex()
+    );
+    assert_eq!(
+        targets["aarch64-unknown-linux-gnu"]["sha256"],
+        linux_aarch64.sha256_hex()
+    );
+}
+
+#[test]
+fn refreshes_locked_versions() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+
+    let old = ReleaseAsset::dfx_tarball("0.13.1", "echo 'dfx 0.13.1'");
+    let new = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    write_lockfile(
+        project_dir.path(),
+        json!({
+            "versions": {
+                "0.13.1": {
+                    ReleaseAsset::host_target("0.13.1"): {
+                        "url": server.url(&old),
+                        "sha256": "0".repeat(64),
+                    }
+                }
+            }
+        }),
+    );
+    server.expect_get(&ReleaseAsset::sha256(&old));
+    server.expect_get(&ReleaseAsset::sha256(&new));
+
+    home_dir
+        .dfxvm()
+        .arg("lock")
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "updated dfx 0.13.1 for {}: {}",
+            ReleaseAsset::host_target("0.13.1"),
+            old.sha256_hex()
+        )))
+        .stderr(contains(format!(
+            "locked dfx 0.14.0 for {}",
+            ReleaseAsset::host_target("0.14.0")
+        )));
+
+    let lockfile = read_lockfile(project_dir.path());
+    assert_eq!(
+        lockfile["versions"]["0.13.1"][ReleaseAsset::host_target("0.13.1")]["sha256"],
+        old.sha256_hex()
+    );
+    assert_eq!(
+        lockfile["versions"]["0.14.0"][ReleaseAsset::host_target("0.14.0")]["sha256"],
+        new.sha256_hex()
+    );
+}
+
+#[test]
+fn lock_requires_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    let project_dir = home_dir.new_project_temp_dir();
+
+    home_dir
+        .dfxvm()
+        .args(["lock", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .failure()
+        .stderr(contains("no dfx.json found"));
+
+    assert!(!project_dir.path().join("dfxvm.lock").exists());
+}
+
+#[test]
+fn lock_requires_versions() {
+    let home_dir = TempHomeDir::new();
+    let project_dir = project_dir(&home_dir, "{}");
+
+    home_dir
+        .dfxvm()
+        .arg("lock")
+        .current_dir(&project_dir)
+        .assert()
+        .failure()
+        .stderr(contains("no versions to lock"));
+}
+
+#[test]
+fn install_uses_locked_checksum() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    write_lockfile(
+        project_dir.path(),
+        json!({
+            "versions": {
+                "0.14.0": {
+                    ReleaseAsset::host_target("0.14.0"): {
+                        "url": server.url(&tarball),
+                        "sha256": tarball.sha256_hex(),
+                    }
+                }
+            }
+        }),
+    );
+    server.expect_get(&tarball);
+    server.expect_no_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains("using checksum from"))
+        .stderr(contains(format!(
+            "verified checksum {}",
+            tarball.sha256_hex()
+        )));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+}
+
+#[test]
+fn install_fails_when_release_does_not_match_lockfile() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+
+    let locked = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    let replaced = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'; echo replaced");
+    write_lockfile(
+        project_dir.path(),
+        json!({
+            "versions": {
+                "0.14.0": {
+                    ReleaseAsset::host_target("0.14.0"): {
+                        "url": server.url(&locked),
+                        "sha256": locked.sha256_hex(),
+                    }
+                }
+            }
+        }),
+    );
+    server.expect_get(&replaced);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "has sha256 {}, but {} expects {}",
+            replaced.sha256_hex(),
+            project_dir
+                .path()
+                .canonicalize()
+                .unwrap()
+                .join("dfxvm.lock")
+                .display(),
+            locked.sha256_hex()
+        )))
+        .stderr(contains("run `dfxvm lock`"));
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn install_of_unlocked_version_uses_published_checksum() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+    write_lockfile(project_dir.path(), json!({ "versions": {} }));
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains("using checksum from").not());
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.15.0"]);
+}
+
+#[test]
+fn dfx_auto_install_uses_locked_checksum() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_auto_install("always");
+    let project_dir = project_dir(&home_dir, r#"{"dfx": "0.14.0"}"#);
+
+    let locked = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'");
+    let replaced = ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'; echo replaced");
+    write_lockfile(
+        project_dir.path(),
+        json!({
+            "versions": {
+                "0.14.0": {
+                    ReleaseAsset::host_target("0.14.0"): {
+                        "url": server.url(&locked),
+                        "sha256": locked.sha256_hex(),
+                    }
+                }
+            }
+        }),
+    );
+    server.expect_get(&replaced);
+
+    home_dir
+        .dfx()
+        .arg("--version")
+        .current_dir(&project_dir)
+        .assert()
+        .failure()
+        .stderr(contains("expects"));
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
@@ -0,0 +1,309 @@
+use crate::common::file_contents::manifest_json;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use predicates::str::*;
+use serde_json::{json, Value};
+use std::path::Path;
+use tempfile::TempDir;
+
+// nothing listens on the discard port
+const UNREACHABLE_DOWNLOAD_URL_TEMPLATE: &str =
+    "http://127.0.0.1:9/any/arbitrary/path/{{version}}/{{basename}}.{{archive-format}}";
+
+// The first server is the preferred mirror, and the second the fallback
+fn mirrors(home_dir: &TempHomeDir) -> (ReleaseServer, ReleaseServer) {
+    let mirror = ReleaseServer::new(home_dir);
+    let fallback = ReleaseServer::new(home_dir);
+    home_dir.settings().write_download_url_templates(&[
+        mirror.download_url_template(),
+        fallback.download_url_template(),
+    ]);
+    home_dir
+        .settings()
+        .write_manifest_urls(&[mirror.manifest_url(), fallback.manifest_url()]);
+    (mirror, fallback)
+}
+
+fn tarball() -> ReleaseAsset {
+    ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'")
+}
+
+fn expect_download(server: &ReleaseServer, tarball: &ReleaseAsset) {
+    server.expect_get(tarball);
+    server.expect_get(&ReleaseAsset::sha256(tarball));
+}
+
+fn locked_project(home_dir: &TempHomeDir, url: &str, tarball: &ReleaseAsset) -> TempDir {
+    let project_dir = home_dir.new_project_temp_dir();
+    std::fs::write(project_dir.path().join("dfx.json"), r#"{"dfx": "0.14.0"}"#).unwrap();
+    let lockfile = json!({
+        "versions": {
+            "0.14.0": {
+                ReleaseAsset::host_target("0.14.0"): {
+                    "url": url,
+                    "sha256": tarball.sha256_hex(),
+                }
+            }
+        }
+    });
+    std::fs::write(project_dir.path().join("dfxvm.lock"), lockfile.to_string()).unwrap();
+    project_dir
+}
+
+fn read_lockfile(project_dir: &Path) -> Value {
+    let contents = std::fs::read_to_string(project_dir.join("dfxvm.lock")).unwrap();
+    serde_json::from_str(&contents).unwrap()
+}
+
+#[test]
+fn downloads_from_first_mirror() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let tarball = tarball();
+    expect_download(&mirror, &tarball);
+    fallback.expect_no_get(&tarball);
+    fallback.expect_no_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains(format!("downloaded {}", mirror.url(&tarball))))
+        .stderr(contains("trying the next mirror").not());
+}
+
+#[test]
+fn falls_back_when_mirror_has_server_error() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let tarball = tarball();
+    mirror.expect_get_respond_server_error(&ReleaseAsset::sha256(&tarball));
+    expect_download(&fallback, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("500 Internal Server Error"))
+        .stderr(contains(format!(
+            "trying the next mirror, {}",
+            fallback.download_url_template()
+        )))
+        .stderr(contains(format!("downloaded {}", fallback.url(&tarball))))
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn falls_back_when_mirror_is_unreachable() {
+    let home_dir = TempHomeDir::new();
+    let fallback = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_download_url_templates(&[
+        UNREACHABLE_DOWNLOAD_URL_TEMPLATE.to_string(),
+        fallback.download_url_template(),
+    ]);
+    let tarball = tarball();
+    expect_download(&fallback, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("trying the next mirror"))
+        .stderr(contains(format!("downloaded {}", fallback.url(&tarball))));
+}
+
+#[test]
+fn falls_back_when_mirror_does_not_have_version() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let tarball = tarball();
+    mirror.expect_get_respond_not_found(&ReleaseAsset::sha256(&tarball));
+    mirror.expect_no_get(&tarball);
+    expect_download(&fallback, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("no such version"))
+        .stderr(contains(format!("downloaded {}", fallback.url(&tarball))));
+}
+
+#[test]
+fn falls_back_on_checksum_mismatch() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let tarball = tarball();
+    let corrupted = ReleaseAsset {
+        contents: b"corrupted".to_vec(),
+        ..tarball.clone()
+    };
+    mirror.expect_get(&ReleaseAsset::sha256(&tarball));
+    mirror.expect_get(&corrupted);
+    expect_download(&fallback, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("checksum did not match"))
+        .stderr(contains(format!("downloaded {}", fallback.url(&tarball))))
+        .stderr(contains("installed dfx 0.14.0"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.14.0"]);
+}
+
+#[test]
+fn reports_error_from_last_mirror() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let tarball = tarball();
+    mirror.expect_get_respond_server_error(&ReleaseAsset::sha256(&tarball));
+    fallback.expect_get_respond_not_found(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("trying the next mirror").count(1))
+        .stderr(contains("no such version"));
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn single_download_url_template_in_a_list() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_download_url_templates(&[server.download_url_template()]);
+    let tarball = tarball();
+    expect_download(&server, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn falls_back_to_next_manifest_mirror() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    mirror.expect_get_manifest_respond_server_error();
+    fallback.expect_get_manifest(&manifest_json("0.5.2"));
+
+    home_dir
+        .dfxvm()
+        .args(["list", "--available"])
+        .assert()
+        .success()
+        .stdout(contains("0.5.2"))
+        .stderr(contains(format!(
+            "trying the next mirror, {}",
+            fallback.manifest_url()
+        )))
+        .stderr(contains(format!(
+            "fetched the manifest from {}",
+            fallback.manifest_url()
+        )));
+}
+
+#[test]
+fn resolves_tag_from_next_manifest_mirror() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    mirror.expect_get_manifest_respond_server_error();
+    fallback.expect_get_manifest(&manifest_json("0.14.0"));
+    let tarball = tarball();
+    mirror.expect_get_respond_server_error(&ReleaseAsset::sha256(&tarball));
+    expect_download(&fallback, &tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "latest"])
+        .assert()
+        .success()
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn lock_records_checksum_from_next_mirror() {
+    let home_dir = TempHomeDir::new();
+    let (mirror, fallback) = mirrors(&home_dir);
+    let project_dir = home_dir.new_project_temp_dir();
+    std::fs::write(project_dir.path().join("dfx.json"), "{}").unwrap();
+    let tarball = tarball();
+    mirror.expect_get_respond_server_error(&ReleaseAsset::sha256(&tarball));
+    fallback.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["lock", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains("trying the next mirror"));
+
+    let lockfile = read_lockfile(project_dir.path());
+    let locked = &lockfile["versions"]["0.14.0"][ReleaseAsset::host_target("0.14.0")];
+    assert_eq!(locked["url"], fallback.url(&tarball));
+    assert_eq!(locked["sha256"], tarball.sha256_hex());
+}
+
+#[test]
+fn locked_install_prefers_mirrors_to_locked_url() {
+    let home_dir = TempHomeDir::new();
+    let locked_from = ReleaseServer::new(&home_dir);
+    let mirror = ReleaseServer::new(&home_dir);
+    let tarball = tarball();
+    let project_dir = locked_project(&home_dir, &locked_from.url(&tarball), &tarball);
+    mirror.expect_get(&tarball);
+    mirror.expect_no_get(&ReleaseAsset::sha256(&tarball));
+    locked_from.expect_no_get(&tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains("using checksum from"))
+        .stderr(contains(format!("downloaded {}", mirror.url(&tarball))));
+}
+
+#[test]
+fn locked_install_falls_back_to_locked_url() {
+    let home_dir = TempHomeDir::new();
+    let locked_from = ReleaseServer::new(&home_dir);
+    let mirror = ReleaseServer::new(&home_dir);
+    let tarball = tarball();
+    let project_dir = locked_project(&home_dir, &locked_from.url(&tarball), &tarball);
+    mirror.expect_get_respond_server_error(&tarball);
+    locked_from.expect_get(&tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .current_dir(&project_dir)
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "trying the next mirror, {}",
+            locked_from.url(&tarball)
+        )))
+        .stderr(contains(format!(
+            "downloaded {}",
+            locked_from.url(&tarball)
+        )));
+}
@@ -0,0 +1,356 @@
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::str::*;
+use std::path::Path;
+
+fn canonical(path: &Path) -> String {
+    path.canonicalize().unwrap().display().to_string()
+}
+
+#[test]
+fn set_override_for_current_dir() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+    home_dir.settings().write_default_version("0.5.6");
+
+    let project = home_dir.new_project_temp_dir();
+    let subdir = project.path().join("a").join("b");
+    std::fs::create_dir_all(&subdir).unwrap();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .assert()
+        .success()
+        .stderr(contains("using existing install for dfx 0.6.3"))
+        .stderr(contains(format!(
+            "set override for {} to dfx 0.6.3",
+            canonical(project.path())
+        )));
+
+    home_dir
+        .dfx()
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout("hi from 0.6.3\n");
+    home_dir
+        .dfx()
+        .current_dir(&subdir)
+        .assert()
+        .success()
+        .stdout("hi from 0.6.3\n");
+
+    // only applies to the directory and its subdirectories
+    let other = home_dir.new_project_temp_dir();
+    home_dir
+        .dfx()
+        .current_dir(&other)
+        .assert()
+        .success()
+        .stdout("hi from 0.5.6\n");
+
+    // doesn't change the default
+    assert_eq!(home_dir.settings().read_default_version(), "0.5.6");
+}
+
+#[test]
+fn set_override_for_path() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    let project = home_dir.new_project_temp_dir();
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .arg("--path")
+        .arg(project.path())
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "set override for {} to dfx 0.6.3",
+            canonical(project.path())
+        )));
+
+    home_dir
+        .dfx()
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout("hi from 0.6.3\n");
+}
+
+#[test]
+fn set_same_override() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+    let project = home_dir.new_project_temp_dir();
+
+    for _ in 0..2 {
+        home_dir
+            .dfxvm()
+            .current_dir(&project)
+            .arg("override")
+            .arg("set")
+            .arg("0.6.3")
+            .assert()
+            .success();
+    }
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "dfx 0.6.3 is already the override for {}",
+            canonical(project.path())
+        )));
+}
+
+#[test]
+fn set_override_for_nonexistent_path() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+    home_dir.settings().write_default_version("0.6.3");
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .arg("--path")
+        .arg(home_dir.path().join("does-not-exist"))
+        .assert()
+        .failure()
+        .stderr(is_match("failed to canonicalize '.*/does-not-exist'").unwrap());
+
+    assert_eq!(home_dir.settings().sorted_keys(), ["default_version"]);
+}
+
+#[test]
+fn set_override_installs_if_not_installed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.2.7", "echo this is dfx 0.2.7");
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+
+    let project = home_dir.new_project_temp_dir();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("set")
+        .arg("0.2.7")
+        .assert()
+        .success()
+        .stderr(contains("installing dfx 0.2.7"))
+        .stderr(contains("set override for"));
+
+    home_dir
+        .dfx()
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout("this is dfx 0.2.7\n");
+}
+
+#[test]
+fn closest_override_wins() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    let project = home_dir.new_project_temp_dir();
+    let subdir = project.path().join("sub");
+    std::fs::create_dir_all(&subdir).unwrap();
+
+    for (version, dir) in [("0.5.6", project.path()), ("0.6.3", &subdir)] {
+        home_dir
+            .dfxvm()
+            .arg("override")
+            .arg("set")
+            .arg(version)
+            .arg("--path")
+            .arg(dir)
+            .assert()
+            .success();
+    }
+
+    home_dir
+        .dfx()
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout("hi from 0.5.6\n");
+    home_dir
+        .dfx()
+        .current_dir(&subdir)
+        .assert()
+        .success()
+        .stdout("hi from 0.6.3\n");
+}
+
+#[test]
+fn list_overrides() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout("");
+
+    let a = home_dir.path().join("a");
+    let b = home_dir.path().join("b");
+    std::fs::create_dir_all(&a).unwrap();
+    std::fs::create_dir_all(&b).unwrap();
+
+    for (version, dir) in [("0.6.3", &b), ("0.5.6", &a)] {
+        home_dir
+            .dfxvm()
+            .arg("override")
+            .arg("set")
+            .arg(version)
+            .arg("--path")
+            .arg(dir)
+            .assert()
+            .success();
+    }
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\t0.5.6\n{}\t0.6.3\n",
+            canonical(&a),
+            canonical(&b)
+        ));
+}
+
+#[test]
+fn unset_override() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+    home_dir.settings().write_default_version("0.5.6");
+
+    let project = home_dir.new_project_temp_dir();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("unset")
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "removed override for {}",
+            canonical(project.path())
+        )));
+
+    home_dir
+        .dfx()
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout("hi from 0.5.6\n");
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("unset")
+        .arg("--path")
+        .arg(project.path())
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "no override set for {}",
+            canonical(project.path())
+        )));
+
+    // removing the last override removes the field
+    assert_eq!(home_dir.settings().sorted_keys(), ["default_version"]);
+}
+
+#[test]
+fn unset_nonexistent_overrides() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+
+    let kept = home_dir.new_project_temp_dir();
+    let removed = home_dir.path().join("removed");
+    std::fs::create_dir_all(&removed).unwrap();
+    let removed_canonical = canonical(&removed);
+
+    for dir in [kept.path(), &removed] {
+        home_dir
+            .dfxvm()
+            .arg("override")
+            .arg("set")
+            .arg("0.5.6")
+            .arg("--path")
+            .arg(dir)
+            .assert()
+            .success();
+    }
+    std::fs::remove_dir(&removed).unwrap();
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("unset")
+        .arg("--nonexistent")
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "removed override for {removed_canonical}"
+        )));
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(format!("{}\t0.5.6\n", canonical(kept.path())));
+
+    home_dir
+        .dfxvm()
+        .arg("override")
+        .arg("unset")
+        .arg("--nonexistent")
+        .assert()
+        .success()
+        .stderr(contains("no overrides for nonexistent directories"));
+}
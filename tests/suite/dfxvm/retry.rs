@@ -0,0 +1,262 @@
+use crate::common::file_contents::manifest_json;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use httptest::http::Response;
+use httptest::responders::{status_code, Responder};
+use predicates::prelude::*;
+use predicates::str::*;
+
+fn tarball() -> ReleaseAsset {
+    ReleaseAsset::dfx_tarball("0.14.0", "echo 'dfx 0.14.0'")
+}
+
+// retry quickly, unless a test says otherwise
+fn server(home_dir: &TempHomeDir) -> ReleaseServer {
+    let server = ReleaseServer::new(home_dir);
+    home_dir.settings().write_retry_base_delay_ms(10);
+    server
+}
+
+fn failures(status: u16, count: usize) -> Vec<Box<dyn Responder>> {
+    (0..count)
+        .map(|_| Box::new(status_code(status)) as Box<dyn Responder>)
+        .collect()
+}
+
+fn retry_after(status: u16, value: &str) -> Box<dyn Responder> {
+    Box::new(
+        Response::builder()
+            .status(status)
+            .header("Retry-After", value)
+            .body(Vec::new())
+            .unwrap(),
+    )
+}
+
+fn ok(asset: &ReleaseAsset) -> Box<dyn Responder> {
+    Box::new(asset.ok_response())
+}
+
+#[test]
+fn retries_unavailable_server() {
+    for status in [429, 502, 503, 504] {
+        let home_dir = TempHomeDir::new();
+        let server = server(&home_dir);
+        let tarball = tarball();
+        let mut responses = failures(status, 2);
+        responses.push(ok(&tarball));
+        server.expect_get_responses(&tarball.url_path, responses);
+        server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+        home_dir
+            .dfxvm()
+            .args(["install", "0.14.0"])
+            .assert()
+            .success()
+            .stderr(contains("retry in").count(2))
+            .stderr(contains("installed dfx 0.14.0"));
+    }
+}
+
+#[test]
+fn does_not_retry_other_errors() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, failures(500, 1));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("500 Internal Server Error"))
+        .stderr(contains("retry in").not());
+}
+
+#[test]
+fn gives_up_after_retries() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, failures(503, 4));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("retry in").count(3))
+        .stderr(contains("503 Service Unavailable"));
+}
+
+#[test]
+fn retries_from_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    home_dir.settings().write_retries(1);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, failures(503, 2));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("retry in").count(1));
+}
+
+#[test]
+fn retries_option_takes_precedence_over_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    home_dir.settings().write_retries(0);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, failures(503, 3));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0", "--retries", "2"])
+        .assert()
+        .failure()
+        .stderr(contains("retry in").count(2));
+}
+
+#[test]
+fn no_retry() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, failures(503, 1));
+
+    home_dir
+        .dfxvm()
+        .args(["--no-retry", "install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("503 Service Unavailable"))
+        .stderr(contains("retry in").not());
+}
+
+#[test]
+fn no_retry_conflicts_with_retries() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0", "--no-retry", "--retries", "2"])
+        .assert()
+        .failure()
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn honors_retry_after() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, vec![retry_after(429, "1"), ok(&tarball)]);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("429 Too Many Requests"))
+        .stderr(contains("retry in 1s"))
+        .stderr(contains("installed dfx 0.14.0"));
+}
+
+#[test]
+fn honors_retry_after_date() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    // a date in the past asks for no wait beyond the usual delay
+    server.expect_get_responses(
+        &tarball.url_path,
+        vec![
+            retry_after(503, "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ok(&tarball),
+        ],
+    );
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .success()
+        .stderr(contains("retry in 10ms"));
+}
+
+#[test]
+fn gives_up_rather_than_wait_past_deadline() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    home_dir.settings().write_retry_deadline_secs(5);
+    let tarball = tarball();
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_responses(&tarball.url_path, vec![retry_after(429, "3600")]);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0"])
+        .assert()
+        .failure()
+        .stderr(contains("429 Too Many Requests"))
+        .stderr(contains("retry in").not());
+}
+
+#[test]
+fn retries_manifest() {
+    let home_dir = TempHomeDir::new();
+    let server = server(&home_dir);
+    let mut responses = failures(502, 1);
+    responses.push(Box::new(
+        Response::builder()
+            .status(200)
+            .body(manifest_json("0.5.2").into_bytes())
+            .unwrap(),
+    ));
+    server.expect_get_responses("/manifest.json", responses);
+
+    home_dir
+        .dfxvm()
+        .args(["list", "--available"])
+        .assert()
+        .success()
+        .stdout(contains("0.5.2"))
+        .stderr(contains("retry in 10ms"));
+}
+
+#[test]
+fn retry_exhausted_before_trying_next_mirror() {
+    let home_dir = TempHomeDir::new();
+    let mirror = server(&home_dir);
+    let fallback = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_download_url_templates(&[
+        mirror.download_url_template(),
+        fallback.download_url_template(),
+    ]);
+    let tarball = tarball();
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    mirror.expect_get_responses(&sha256.url_path, failures(503, 2));
+    fallback.expect_get(&sha256);
+    fallback.expect_get(&tarball);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.14.0", "--retries", "1"])
+        .assert()
+        .success()
+        .stderr(contains("retry in").count(1))
+        .stderr(contains("trying the next mirror"))
+        .stderr(contains("installed dfx 0.14.0"));
+}
@@ -0,0 +1,89 @@
+use crate::common::file_contents::manifest_json_with_tags;
+use crate::common::paths::prepend_to_minimal_path;
+use crate::common::{ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::str::*;
+
+#[test]
+fn sets_dfx_version_and_path() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'this is dfx 0.6.3'");
+    home_dir.settings().write_default_version("0.5.6");
+
+    let expected_path = prepend_to_minimal_path(home_dir.dfx_version_dir("0.6.3"));
+    home_dir
+        .dfxvm()
+        .args(["run", "0.6.3", "--", "sh", "-c"])
+        .arg("echo \"$DFX_VERSION\"; echo \"$PATH\"; dfx")
+        .assert()
+        .success()
+        .stdout(format!("0.6.3\n{expected_path}\nthis is dfx 0.6.3\n"));
+}
+
+#[test]
+fn passes_arguments_and_exit_code() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo \"args: $*\"; exit 3");
+
+    home_dir
+        .dfxvm()
+        .args(["run", "0.6.3", "--", "dfx", "deploy", "--network", "ic"])
+        .assert()
+        .code(3)
+        .stdout("args: deploy --network ic\n");
+}
+
+#[test]
+fn tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[("latest", "0.6.3")]));
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'this is dfx 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .args(["run", "latest", "--", "dfx"])
+        .assert()
+        .success()
+        .stdout("this is dfx 0.6.3\n");
+}
+
+#[test]
+fn not_installed() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .args(["run", "0.6.3", "--", "dfx"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains(
+            "dfx 0.6.3 is not installed.  To install it, run: dfxvm install 0.6.3",
+        ));
+}
+
+#[test]
+fn command_not_found() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'this is dfx 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .args(["run", "0.6.3", "--", "no-such-command"])
+        .assert()
+        .failure()
+        .stderr(contains("failed to execute"));
+}
+
+#[test]
+fn requires_command() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .args(["run", "0.6.3"])
+        .assert()
+        .failure()
+        .stderr(contains("required arguments were not provided"));
+}
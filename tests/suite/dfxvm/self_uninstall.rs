@@ -120,8 +120,8 @@ fn all_process_exe_paths() -> Vec<PathBuf> {
     let mut info = sysinfo::System::new();
     info.refresh_processes(ProcessesToUpdate::All, true);
     info.processes()
-        .iter()
-        .filter_map(|(_pid, proc)| proc.exe().map(|p| p.to_path_buf()))
+        .values()
+        .filter_map(|proc| proc.exe().map(|p| p.to_path_buf()))
         .collect()
 }
 
@@ -143,6 +143,11 @@ path = "main.rs"
 "#;
     let main_rs = r#"
 fn main() {
+    // passes the smoke test that runs when installing
+    if std::env::args().nth(1).as_deref() == Some("--version") {
+        println!("dfx 0.15.0");
+        return;
+    }
     loop {
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
@@ -1,5 +1,5 @@
 use crate::common::file_contents::dist_manifest_json;
-use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir, TestKey};
 use assert_cmd::prelude::*;
 use predicates::str::contains;
 use semver::Version;
@@ -58,6 +58,38 @@ fn dfxvm_cleans_up_after_self_update() {
     assert_eq!(home_dir.installed_binaries(), ["dfx", "dfxvm"]);
 }
 
+#[test]
+fn self_update_relinks_shims() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    let tarball = ReleaseAsset::altered_dfxvm_tarball();
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    server.expect_get(&tarball);
+    server.expect_get(&sha256);
+    server.expect_get_dist_manifest(&dist_manifest_json(&newer_version()));
+
+    home_dir.install_dfxvm_bin();
+    home_dir.install_dfxvm_bin_as_dfx_proxy();
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "add", "dfx-stable", "0.6.3"])
+        .assert()
+        .success();
+
+    home_dir
+        .installed_dfxvm()
+        .args(["self", "update"])
+        .assert()
+        .success();
+
+    let shim = std::fs::read(home_dir.installed_shim_path("dfx-stable")).unwrap();
+    assert!(
+        shim == ReleaseAsset::altered_dfxvm_binary(),
+        "shim is not the altered dfxvm"
+    );
+}
+
 fn self_update(to_version: &str) -> TempHomeDir {
     let home_dir = TempHomeDir::new();
     let server = ReleaseServer::new(&home_dir);
@@ -172,3 +204,34 @@ fn incorrect_sha256() {
         .stderr(contains("checking for self-update"))
         .stderr(contains("checksum did not match"));
 }
+
+#[test]
+fn self_update_verifies_signature() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+
+    let tarball = ReleaseAsset::altered_dfxvm_tarball();
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get(&ReleaseAsset::signature(&tarball, &TestKey::new(2)));
+    server.expect_get_dist_manifest(&dist_manifest_json(&newer_version()));
+
+    home_dir.install_dfxvm_bin();
+    home_dir.install_dfxvm_bin_as_dfx_proxy();
+    let built_dfxvm = std::fs::read(crate::common::dfxvm_path()).unwrap();
+
+    home_dir
+        .installed_dfxvm()
+        .args(["self", "update"])
+        .assert()
+        .failure()
+        .stderr(contains("is not from a trusted key"));
+
+    let installed_dfxvm = std::fs::read(home_dir.installed_dfxvm_path()).unwrap();
+    assert!(
+        installed_dfxvm == built_dfxvm,
+        "installed dfxvm was replaced"
+    );
+}
@@ -0,0 +1,124 @@
+use crate::common::TempHomeDir;
+use assert_cmd::prelude::*;
+use predicates::str::*;
+
+#[test]
+fn add_shim() {
+    let home_dir = TempHomeDir::new();
+    home_dir.install_dfxvm_bin();
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'this is dfx 0.6.3'");
+    home_dir.create_executable_dfx_script("0.7.1", "echo 'this is dfx 0.7.1'");
+    home_dir.settings().write_default_version("0.7.1");
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "add", "dfx-stable", "0.6.3"])
+        .assert()
+        .success()
+        .stderr(contains("added dfx-stable, which runs dfx 0.6.3"));
+
+    home_dir
+        .new_command(home_dir.installed_shim_path("dfx-stable"))
+        .assert()
+        .success()
+        .stdout("this is dfx 0.6.3\n");
+}
+
+#[test]
+fn shims_side_by_side() {
+    let home_dir = TempHomeDir::new();
+    home_dir.install_dfxvm_bin();
+    home_dir.create_executable_dfx_script("0.23.1", "echo 'this is dfx 0.23.1'");
+    home_dir.create_executable_dfx_script("0.25.0", "echo 'this is dfx 0.25.0'");
+
+    for (name, version) in [("dfx-0.23", "0.23"), ("dfx-0.25", "0.25")] {
+        home_dir
+            .installed_dfxvm()
+            .args(["shim", "add", name, version])
+            .assert()
+            .success();
+    }
+
+    home_dir
+        .new_command(home_dir.installed_shim_path("dfx-0.23"))
+        .assert()
+        .success()
+        .stdout("this is dfx 0.23.1\n");
+    home_dir
+        .new_command(home_dir.installed_shim_path("dfx-0.25"))
+        .assert()
+        .success()
+        .stdout("this is dfx 0.25.0\n");
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "list"])
+        .assert()
+        .success()
+        .stdout("dfx-0.23\t^0.23\ndfx-0.25\t^0.25\n");
+}
+
+#[test]
+fn remove_shim() {
+    let home_dir = TempHomeDir::new();
+    home_dir.install_dfxvm_bin();
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "add", "dfx-stable", "0.6.3"])
+        .assert()
+        .success();
+    assert!(home_dir.installed_shim_path("dfx-stable").exists());
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "remove", "dfx-stable"])
+        .assert()
+        .success()
+        .stderr(contains("removed dfx-stable"));
+    assert!(!home_dir.installed_shim_path("dfx-stable").exists());
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "list"])
+        .assert()
+        .success()
+        .stdout("");
+
+    home_dir
+        .installed_dfxvm()
+        .args(["shim", "remove", "dfx-stable"])
+        .assert()
+        .success()
+        .stderr(contains("no shim named dfx-stable"));
+}
+
+#[test]
+fn invalid_name() {
+    let home_dir = TempHomeDir::new();
+    home_dir.install_dfxvm_bin();
+
+    for name in ["stable", "dfx-", "dfx-a/b"] {
+        home_dir
+            .installed_dfxvm()
+            .args(["shim", "add", name, "0.6.3"])
+            .assert()
+            .failure()
+            .stderr(contains(format!(
+                "invalid shim name '{name}': must start with 'dfx-'"
+            )));
+    }
+}
+
+#[test]
+fn dfxvm_not_installed() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .args(["shim", "add", "dfx-stable", "0.6.3"])
+        .assert()
+        .failure()
+        .stderr(contains("dfxvm is not installed at"));
+    assert!(!home_dir.installed_shim_path("dfx-stable").exists());
+}
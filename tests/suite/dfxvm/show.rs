@@ -0,0 +1,143 @@
+use crate::common::TempHomeDir;
+use assert_cmd::prelude::*;
+use predicates::str::*;
+
+#[test]
+fn nothing_configured() {
+    let home_dir = TempHomeDir::new();
+    let tempdir = home_dir.new_project_temp_dir();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&tempdir)
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(contains("  command line:    not specified\n"))
+        .stdout(contains("  DFX_VERSION:     not set\n"))
+        .stdout(contains("  override:        not set\n"))
+        .stdout(contains("  dfx.json:        not found\n"))
+        .stdout(contains("  default_version: not set\n"))
+        .stdout(contains("active version: none\n"));
+}
+
+#[test]
+fn default_version() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.5.6");
+    let tempdir = home_dir.new_project_temp_dir();
+    let settings_path = home_dir.config_dir().join("version-manager.json");
+
+    home_dir
+        .dfxvm()
+        .current_dir(&tempdir)
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(contains(format!(
+            "  default_version: 0.5.6 ({})\n",
+            settings_path.display()
+        )))
+        .stdout(contains(format!(
+            "active version: dfx 0.5.6 (from default_version in {})\n",
+            settings_path.display()
+        )));
+}
+
+#[test]
+fn shows_every_source_and_the_winner() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.7.2", "...");
+    home_dir.create_executable_dfx_script("0.7.3", "...");
+    home_dir.settings().write_default_version("0.5.6");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    let project = tempdir.path().canonicalize().unwrap();
+    std::fs::write(project.join("dfx.json"), r#"{"dfx": "^0.7"}"#).unwrap();
+    std::fs::write(project.join(".dfx-version"), "0.8.1").unwrap();
+
+    let show = |dfx_version: Option<&str>| {
+        let mut cmd = home_dir.dfxvm();
+        cmd.current_dir(&project).arg("show");
+        if let Some(dfx_version) = dfx_version {
+            cmd.env("DFX_VERSION", dfx_version);
+        }
+        cmd.assert()
+            .success()
+            .stdout(contains(format!(
+                "  dfx.json:        ^0.7 ({})\n",
+                project.join("dfx.json").display()
+            )))
+            .stdout(contains(format!(
+                "  toolchain file:  0.8.1 ({})\n",
+                project.join(".dfx-version").display()
+            )))
+            .stdout(contains("  default_version: 0.5.6 ("))
+    };
+
+    show(None).stdout(contains(format!(
+        "active version: dfx 0.7.3 (from {})\n",
+        project.join("dfx.json").display()
+    )));
+
+    show(Some("0.6.1"))
+        .stdout(contains("  DFX_VERSION:     0.6.1\n"))
+        .stdout(contains(
+            "active version: dfx 0.6.1 (from DFX_VERSION environment variable)\n",
+        ));
+}
+
+#[test]
+fn version_parameter_acts_as_command_line() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.5.6");
+
+    home_dir
+        .dfxvm()
+        .arg("show")
+        .arg("--version")
+        .arg("0.9.1")
+        .env("DFX_VERSION", "0.6.1")
+        .assert()
+        .success()
+        .stdout(contains("  command line:    0.9.1\n"))
+        .stdout(contains("  DFX_VERSION:     0.6.1\n"))
+        .stdout(contains("active version: dfx 0.9.1 (from command line)\n"));
+}
+
+#[test]
+fn shows_override() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.6.3", "...");
+    let tempdir = home_dir.new_project_temp_dir();
+    let project = tempdir.path().canonicalize().unwrap();
+    std::fs::write(project.join("dfx.json"), r#"{}"#).unwrap();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("override")
+        .arg("set")
+        .arg("0.6.3")
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&project)
+        .arg("show")
+        .assert()
+        .success()
+        .stdout(contains(format!(
+            "  override:        0.6.3 ({})\n",
+            project.display()
+        )))
+        .stdout(contains(format!(
+            "  dfx.json:        {} has no dfx field\n",
+            project.join("dfx.json").display()
+        )))
+        .stdout(contains(format!(
+            "active version: dfx 0.6.3 (from override for {})\n",
+            project.display()
+        )));
+}
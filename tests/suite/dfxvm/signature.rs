@@ -0,0 +1,351 @@
+use crate::common::file_contents::manifest_json_with_versions;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir, TestKey};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use predicates::str::*;
+
+fn expect_signed_install(server: &ReleaseServer, key: &TestKey) {
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get(&ReleaseAsset::signature(&tarball, key));
+}
+
+#[test]
+fn install_without_keys_does_not_verify_signatures() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_no_get(&ReleaseAsset::signature(&tarball, &TestKey::new(1)));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("signature").not());
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn install_verifies_signature() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+    expect_signed_install(&server, &key);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(is_match("verified signature .*dfx-.*.tar.gz.minisig").unwrap());
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn install_rejects_signature_from_untrusted_key() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_trusted_keys(&[TestKey::new(1).public_key()]);
+    expect_signed_install(&server, &TestKey::new(2));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("is not from a trusted key"));
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn install_rejects_signature_of_other_contents() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let other = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is not dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get(&ReleaseAsset {
+        contents: key.sign(&other.contents).into_bytes(),
+        ..ReleaseAsset::signature(&tarball, &key)
+    });
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(is_match("signature .*.minisig does not match").unwrap());
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn install_requires_signature() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_trusted_keys(&[TestKey::new(1).public_key()]);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let signature = ReleaseAsset::signature(&tarball, &TestKey::new(1));
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_respond_not_found(&signature);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(is_match("no signature at .*.tar.gz.minisig").unwrap());
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn install_accepts_either_key_during_rotation() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let old_key = TestKey::new(1);
+    let new_key = TestKey::new(2);
+    home_dir
+        .settings()
+        .write_trusted_keys(&[old_key.public_key(), new_key.public_key()]);
+    expect_signed_install(&server, &new_key);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("verified signature"));
+}
+
+#[test]
+fn install_rejects_revoked_key() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let old_key = TestKey::new(1);
+    let new_key = TestKey::new(2);
+    home_dir
+        .settings()
+        .write_trusted_keys(&[old_key.public_key(), new_key.public_key()]);
+    home_dir
+        .settings()
+        .write_revoked_keys(&[old_key.public_key()]);
+    expect_signed_install(&server, &old_key);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("is not from a trusted key"));
+}
+
+#[test]
+fn install_fails_when_only_key_is_revoked() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+    home_dir.settings().write_revoked_keys(&[key.public_key()]);
+
+    // not even an unsigned release is accepted
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_no_get(&tarball);
+    server.expect_no_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "signature verification is enabled but no trusted keys remain",
+        ));
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn verification_without_keys_is_an_error() {
+    let home_dir = TempHomeDir::new();
+    let _server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_verify_signatures(true);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("latest")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "signature verification is enabled but no trusted keys remain",
+        ));
+    assert!(!home_dir.dfx_version_dir("0.15.0").exists());
+}
+
+#[test]
+fn cached_download_is_verified() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get_times(&ReleaseAsset::sha256(&tarball), 2);
+    server.expect_get(&ReleaseAsset::signature(&tarball, &TestKey::new(2)));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success();
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("0.15.0")
+        .assert()
+        .success();
+
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("using cached download"))
+        .stderr(contains("is not from a trusted key"));
+}
+
+#[test]
+fn verification_can_be_turned_off() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir
+        .settings()
+        .write_trusted_keys(&[TestKey::new(1).public_key()]);
+    home_dir.settings().write_verify_signatures(false);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .success()
+        .stderr(contains("verified signature").not());
+}
+
+#[test]
+fn invalid_trusted_key() {
+    let home_dir = TempHomeDir::new();
+    home_dir
+        .settings()
+        .write_trusted_keys(&["not a key".to_string()]);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15.0")
+        .assert()
+        .failure()
+        .stderr(contains("invalid minisign public key 'not a key'"));
+}
+
+#[test]
+fn manifest_signature_is_verified() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+
+    let manifest = manifest_json_with_versions("0.15.0", &["0.14.2", "0.15.0"]);
+    server.expect_get_manifest(&manifest);
+    server.expect_get_manifest_signature(&manifest, &key);
+    expect_signed_install(&server, &key);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15")
+        .assert()
+        .success()
+        .stderr(contains("verified signature").count(2))
+        .stderr(contains("selected dfx 0.15.0"));
+}
+
+#[test]
+fn manifest_with_bad_signature_is_rejected() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+
+    let manifest = manifest_json_with_versions("0.15.0", &["0.14.2", "0.15.0"]);
+    let tampered = manifest_json_with_versions("0.15.0", &["0.14.2", "0.15.0", "0.15.9"]);
+    server.expect_get_manifest(&tampered);
+    server.expect_get_manifest_signature(&manifest, &key);
+
+    home_dir
+        .dfxvm()
+        .arg("install")
+        .arg("0.15")
+        .assert()
+        .failure()
+        .stderr(contains("manifest.json.minisig does not match"));
+}
+
+#[test]
+fn install_from_file_verifies_signature() {
+    let home_dir = TempHomeDir::new();
+    let key = TestKey::new(1);
+    home_dir.settings().write_trusted_keys(&[key.public_key()]);
+
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    let tarball_path = home_dir.join(&tarball.filename);
+    std::fs::write(&tarball_path, &tarball.contents).unwrap();
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    std::fs::write(home_dir.join(&sha256.filename), &sha256.contents).unwrap();
+
+    let mut install = home_dir.dfxvm();
+    install
+        .arg("install")
+        .arg("0.15.0")
+        .arg("--from-file")
+        .arg(&tarball_path);
+    install
+        .assert()
+        .failure()
+        .stderr(contains("no signature for"));
+
+    let signature = ReleaseAsset::signature(&tarball, &key);
+    std::fs::write(home_dir.join(&signature.filename), &signature.contents).unwrap();
+    install
+        .assert()
+        .success()
+        .stderr(contains("verified signature"));
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
@@ -0,0 +1,276 @@
+use crate::common::file_contents::bash_script;
+use crate::common::{ReleaseAsset, ReleaseServer, TempHomeDir};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use predicates::str::*;
+
+// a target whose binaries can't run on this machine
+fn foreign_target() -> &'static str {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        "aarch64-unknown-linux-gnu"
+    } else {
+        "x86_64-unknown-linux-gnu"
+    }
+}
+
+fn dfx_script() -> Vec<u8> {
+    bash_script("echo 'this is dfx 0.15.0'").into_bytes()
+}
+
+fn expect_install(server: &ReleaseServer, target: &str) {
+    let tarball = ReleaseAsset::dfx_tarball_for_target("0.15.0", target, &dfx_script());
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+}
+
+#[test]
+fn install_for_foreign_target() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let target = foreign_target();
+    expect_install(&server, target);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", target])
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "not running dfx 0.15.0, which is built for {target}"
+        )))
+        .stderr(contains("installed dfx 0.15.0"));
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(format!("0.15.0 ({target})\n"));
+}
+
+#[test]
+fn install_for_target_from_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let target = foreign_target();
+    home_dir.settings().write_target(target);
+    expect_install(&server, target);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0"])
+        .assert()
+        .success()
+        .stderr(contains(format!("built for {target}")));
+}
+
+#[test]
+fn target_option_overrides_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_target(foreign_target());
+    expect_install(&server, ReleaseAsset::host_target("0.15.0"));
+
+    home_dir
+        .dfxvm()
+        .args([
+            "install",
+            "0.15.0",
+            "--target",
+            ReleaseAsset::host_target("0.15.0"),
+        ])
+        .assert()
+        .success()
+        .stderr(contains("checked that dfx 0.15.0 runs"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn install_musl_build() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let target = ReleaseAsset::host_target("0.15.0").replace("-gnu", "-musl");
+    expect_install(&server, &target);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", &target])
+        .assert()
+        .success()
+        .stderr(contains("checked that dfx 0.15.0 runs"));
+
+    home_dir
+        .dfx()
+        .env("DFX_VERSION", "0.15.0")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.15.0\n");
+}
+
+#[test]
+fn binary_must_match_target() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    // a 64-bit little-endian x86_64 ELF header, labelled as aarch64
+    let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+    elf.resize(18, 0);
+    elf.extend(62u16.to_le_bytes());
+    elf.resize(64, 0);
+    let tarball = ReleaseAsset::dfx_tarball_for_target("0.15.0", "aarch64-unknown-linux-gnu", &elf);
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", "aarch64-unknown-linux-gnu"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "the archive's dfx binary is built for x86_64-linux",
+        ));
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
+
+#[test]
+fn invalid_target() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", "linux"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid target triple 'linux'"));
+}
+
+#[test]
+fn already_installed_for_other_target() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.15.0", "echo 'this is dfx 0.15.0'");
+    let target = foreign_target();
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", target])
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "dfx 0.15.0 is already installed for {}.  To install it for {target}, first run: dfxvm uninstall 0.15.0",
+            ReleaseAsset::host_target("0.15.0")
+        )));
+
+    home_dir
+        .dfxvm()
+        .args([
+            "install",
+            "0.15.0",
+            "--target",
+            ReleaseAsset::host_target("0.15.0"),
+        ])
+        .assert()
+        .success()
+        .stderr(contains("dfx 0.15.0 is already installed"));
+}
+
+#[test]
+fn proxy_refuses_foreign_target() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let target = foreign_target();
+    expect_install(&server, target);
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", target])
+        .assert()
+        .success();
+
+    home_dir
+        .dfx()
+        .env("DFX_VERSION", "0.15.0")
+        .arg("--version")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains(format!(
+            "dfx 0.15.0 is installed for {target}, so it can't run on this machine, which is {}.",
+            ReleaseAsset::host_target("0.15.0")
+        )))
+        .stderr(contains(format!(
+            "dfxvm uninstall 0.15.0 && dfxvm install 0.15.0 --target {}",
+            ReleaseAsset::host_target("0.15.0")
+        )));
+
+    home_dir
+        .dfxvm()
+        .args(["run", "0.15.0", "--", "dfx", "--version"])
+        .assert()
+        .failure()
+        .stderr(contains(format!(
+            "dfx 0.15.0 is installed for {target}, so it can't run on this machine"
+        )));
+}
+
+#[test]
+fn proxy_auto_installs_for_this_machine() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    home_dir.settings().write_target(foreign_target());
+    home_dir.settings().write_auto_install("always");
+    expect_install(&server, ReleaseAsset::host_target("0.15.0"));
+
+    home_dir
+        .dfx()
+        .env("DFX_VERSION", "0.15.0")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout("this is dfx 0.15.0\n");
+
+    home_dir
+        .dfxvm()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout("0.15.0\n");
+}
+
+#[test]
+fn install_from_file_for_target() {
+    let home_dir = TempHomeDir::new();
+    let target = foreign_target();
+    let tarball = ReleaseAsset::dfx_tarball_for_target("0.15.0", target, &dfx_script());
+    let sha256 = ReleaseAsset::sha256(&tarball);
+    let tarball_path = home_dir.join(&tarball.filename);
+    std::fs::write(&tarball_path, &tarball.contents).unwrap();
+    std::fs::write(home_dir.join(&sha256.filename), &sha256.contents).unwrap();
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--target", target, "--from-file"])
+        .arg(&tarball_path)
+        .assert()
+        .success()
+        .stderr(contains(format!("built for {target}")));
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
+#[test]
+fn foreign_target_is_not_warmed() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    let target = foreign_target();
+    expect_install(&server, target);
+
+    home_dir
+        .dfxvm()
+        .args(["install", "0.15.0", "--warm-cache", "--target", target])
+        .assert()
+        .success()
+        .stderr(contains(format!(
+            "not warming the dfx cache for dfx 0.15.0, which is built for {target}"
+        )))
+        .stderr(contains("warmed").not());
+}
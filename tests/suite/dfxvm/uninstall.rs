@@ -1,4 +1,4 @@
-use crate::common::TempHomeDir;
+use crate::common::{file_contents::manifest_json_with_tags, HeldLock, ReleaseServer, TempHomeDir};
 use assert_cmd::prelude::*;
 use predicates::str::*;
 
@@ -19,6 +19,55 @@ fn normal() {
     assert_eq!(home_dir.dfx_version_dirs(), vec!["0.6.7"]);
 }
 
+#[test]
+fn uninstall_tag() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+    server.expect_get_manifest(&manifest_json_with_tags(&[
+        ("latest", "0.15.0"),
+        ("beta", "0.16.0-beta.0"),
+    ]));
+    home_dir.create_executable_dfx_script("0.15.0", "echo 'this is dfx 0.15.0'");
+    home_dir.create_executable_dfx_script("0.16.0-beta.0", "echo 'this is dfx 0.16.0-beta.0'");
+
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("beta")
+        .assert()
+        .success()
+        .stderr(contains("uninstalled dfx 0.16.0-beta.0"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.15.0"]);
+}
+
+#[test]
+fn linked_toolchain_takes_precedence_over_tag() {
+    let home_dir = TempHomeDir::new();
+    // no manifest is expected, so fetching one would fail the test
+    let _server = ReleaseServer::new(&home_dir);
+    home_dir.create_executable_dfx_script("0.15.0", "echo 'this is dfx 0.15.0'");
+    let bin_path = home_dir.create_local_dfx_build("dfx-build", "echo 'this is a dev build'");
+    home_dir
+        .dfxvm()
+        .arg("link")
+        .arg("latest")
+        .arg(&bin_path)
+        .assert()
+        .success();
+
+    home_dir
+        .dfxvm()
+        .arg("uninstall")
+        .arg("latest")
+        .assert()
+        .success()
+        .stderr(contains("uninstalled dfx latest"));
+
+    assert_eq!(home_dir.dfx_version_dirs(), vec!["0.15.0"]);
+    assert!(bin_path.exists());
+}
+
 #[test]
 fn version_not_installed() {
     let home_dir = TempHomeDir::new();
@@ -91,3 +140,20 @@ fn uninstall_dir_exists_as_file() {
     assert!(!home_dir.dfx_version_dir("0.3.6").exists());
     assert_eq!(home_dir.dfx_version_dirs(), vec!["0.7.2"]);
 }
+
+#[test]
+fn waits_for_toolchain_lock() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.3.2", "greetings from dfx 0.3.2");
+
+    let lock = HeldLock::acquire(&home_dir.locks_dir().join("dfx-0.3.2.lock"), 2718);
+    let mut cmd = home_dir.dfxvm();
+    cmd.arg("uninstall").arg("0.3.2");
+    lock.release_when_waited_for(cmd)
+        .assert()
+        .success()
+        .stderr(contains("waiting for lock held by pid 2718"))
+        .stderr(contains("uninstalled dfx 0.3.2"));
+
+    assert!(home_dir.dfx_version_dirs().is_empty());
+}
@@ -0,0 +1,89 @@
+use crate::common::TempHomeDir;
+use assert_cmd::prelude::*;
+use predicates::str::*;
+
+#[test]
+fn default_version() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.settings().write_default_version("0.5.6");
+
+    home_dir
+        .dfxvm()
+        .arg("which")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            home_dir.installed_dfx_path("0.5.6").display()
+        ));
+}
+
+#[test]
+fn version_from_dfx_json() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+    home_dir.settings().write_default_version("0.5.6");
+
+    let tempdir = home_dir.new_project_temp_dir();
+    std::fs::write(tempdir.path().join("dfx.json"), r#"{"dfx": "0.6.3"}"#).unwrap();
+
+    home_dir
+        .dfxvm()
+        .current_dir(&tempdir)
+        .arg("which")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            home_dir.installed_dfx_path("0.6.3").display()
+        ));
+}
+
+#[test]
+fn version_from_parameter() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.5.6", "echo 'hi from 0.5.6'");
+    home_dir.create_executable_dfx_script("0.6.3", "echo 'hi from 0.6.3'");
+
+    home_dir
+        .dfxvm()
+        .arg("which")
+        .arg("--version")
+        .arg("0.6.3")
+        .env("DFX_VERSION", "0.5.6")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            home_dir.installed_dfx_path("0.6.3").display()
+        ));
+}
+
+#[test]
+fn not_installed() {
+    let home_dir = TempHomeDir::new();
+    home_dir.settings().write_default_version("0.5.6");
+
+    home_dir
+        .dfxvm()
+        .arg("which")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("error: dfx 0.5.6 is not installed\n");
+}
+
+#[test]
+fn no_version() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm()
+        .arg("which")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(contains("unable to determine which dfx version to use"));
+}
@@ -84,6 +84,27 @@ fn default_installation() {
         .stdout("0.15.0\n");
 }
 
+#[test]
+fn default_installation_with_default_signature_settings() {
+    let home_dir = TempHomeDir::new();
+    let server = ReleaseServer::new(&home_dir);
+
+    // releases are unsigned, and nothing about signatures is configured
+    let tarball = ReleaseAsset::dfx_tarball("0.15.0", "echo 'this is dfx 0.15.0'");
+    server.expect_get(&tarball);
+    server.expect_get(&ReleaseAsset::sha256(&tarball));
+    server.expect_get_manifest(&manifest_json("0.15.0"));
+
+    home_dir
+        .dfxvm_init()
+        .arg("--yes")
+        .assert()
+        .success()
+        .stderr(contains("signature").not());
+
+    assert!(home_dir.installed_dfx_path("0.15.0").exists());
+}
+
 #[test]
 fn specific_dfx_version() {
     let home_dir = TempHomeDir::new();
@@ -15,6 +15,49 @@ fn dispatch_to_dfx() {
         .stdout("Hello, world! (dfx 0.4.6 script)\n");
 }
 
+#[test]
+fn dispatch_to_versioned_dfx() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.4.6", "echo \"dfx 0.4.6 script: $*\"");
+    home_dir.create_executable_dfx_script("0.4.7", "echo \"dfx 0.4.7 script: $*\"");
+    home_dir.settings().write_default_version("0.4.7");
+
+    // all arguments are passed through, even one that looks like +<version>
+    home_dir
+        .dfxvm_as_command_named("dfx-0.4.6")
+        .args(["+0.4.7", "deploy"])
+        .assert()
+        .success()
+        .stdout("dfx 0.4.6 script: +0.4.7 deploy\n");
+}
+
+#[test]
+fn dispatch_to_versioned_dfx_with_requirement() {
+    let home_dir = TempHomeDir::new();
+    home_dir.create_executable_dfx_script("0.4.6", "echo 'dfx 0.4.6 script'");
+    home_dir.create_executable_dfx_script("0.4.9", "echo 'dfx 0.4.9 script'");
+    home_dir.create_executable_dfx_script("0.5.0", "echo 'dfx 0.5.0 script'");
+
+    home_dir
+        .dfxvm_as_command_named("dfx-0.4")
+        .assert()
+        .success()
+        .stdout("dfx 0.4.9 script\n");
+}
+
+#[test]
+fn dispatch_to_versioned_dfx_unparseable() {
+    let home_dir = TempHomeDir::new();
+
+    home_dir
+        .dfxvm_as_command_named("dfx-0.4.x.y")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "failed to parse version from program name 'dfx-0.4.x.y'",
+        ));
+}
+
 #[test]
 fn dispatch_to_dfxvm() {
     let home_dir = TempHomeDir::new();
@@ -23,7 +66,7 @@ fn dispatch_to_dfxvm() {
 
     cmd.assert()
         .success()
-        .stdout(contains("Usage: dfxvm <COMMAND>"));
+        .stdout(contains("Usage: dfxvm [OPTIONS] <COMMAND>"));
 }
 
 #[test]
@@ -56,6 +99,6 @@ fn dispatch_to_unknown() {
     cmd.assert()
         .failure()
         .stderr(contains(
-            "error: unrecognized executable name 'called-something-else'; expect one of: dfx, dfxvm, dfxvm-init",
+            "error: unrecognized executable name 'called-something-else'; expect one of: dfx, dfx-<version>, dfxvm, dfxvm-init",
         ));
 }